@@ -8,7 +8,7 @@
 #![allow(dead_code)]
 use point::{*};
 use point::SpaceTime;
-use chrono::{Local};
+use chrono::{Utc};
 use std::fmt::*;
 
 /*-----------------------------------------------------------
@@ -34,7 +34,7 @@ fn main() {
     print!("\n  -- create Point<i32> and display with Display format --");
     let mut pt = Point::<i32>::new();
     pt.set_coordinates(&[1, 2, 3]);
-    pt.set_time(Local::now());
+    pt.set_time(Utc::now());
     pt.set_name("pt");
     print!("\n  pt = {}", &pt);
     println!();
@@ -0,0 +1,310 @@
+/////////////////////////////////////////////////////////////
+// point::cpa.rs - Closest approach and conflict detection //
+/////////////////////////////////////////////////////////////
+/*
+   Closest point of approach (CPA) between two objects whose
+   motion is given by Trajectory<f64> samples.  Between samples
+   each object moves in a straight line, so on every interval
+   where both are on straight segments the separation is a
+   quadratic in time and its minimum is found exactly.
+   - closest_approach(a, b) searches the time span both
+     trajectories cover.
+   - ConflictDetector checks every pair of tracks over a
+     look-ahead window and reports each episode where they are
+     closer than both the horizontal (x, y) and the vertical
+     (z) separation minima.  By default a track that ends
+     before the window does is extrapolated along its last
+     segment.
+   The detector compares all pairs, n * (n - 1) / 2 of them.
+*/
+use super::trajectory::seconds;
+use super::{SpaceTime, Trajectory};
+use chrono::{DateTime, Duration, Utc};
+
+/*-- separation at the time of closest approach --*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Approach {
+    pub time: DateTime<Utc>,
+    /*-- 3-D distance --*/
+    pub distance: f64,
+    /*-- distance in x, y --*/
+    pub horizontal: f64,
+    /*-- absolute difference in z --*/
+    pub vertical: f64,
+}
+
+/*-- straight motion from time t0 to t1, seconds from an origin --*/
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    t0: f64,
+    t1: f64,
+    p: [f64; 3],
+    v: [f64; 3],
+}
+impl Piece {
+    fn at(&self, t: f64) -> [f64; 3] {
+        let d = t - self.t0;
+        [self.p[0] + self.v[0] * d, self.p[1] + self.v[1] * d, self.p[2] + self.v[2] * d]
+    }
+}
+/*-----------------------------------------------------------
+  straight pieces of tr between origin and end
+  - extend continues the last segment's velocity to end
+*/
+fn pieces(tr: &Trajectory<f64>, origin: DateTime<Utc>, end: DateTime<Utc>, extend: bool) -> Vec<Piece> {
+    let s = tr.samples();
+    let secs = |t: DateTime<Utc>| seconds(t - origin);
+    let horizon = secs(end);
+    let mut out = Vec::new();
+    let mut last_v = [0.0; 3];
+    for w in s.windows(2) {
+        let (t0, t1) = (secs(w[0].get_time()), secs(w[1].get_time()));
+        if t1 <= t0 {
+            continue;
+        }
+        let (a, b) = (w[0].get_coordinates(), w[1].get_coordinates());
+        let v = [(b[0] - a[0]) / (t1 - t0), (b[1] - a[1]) / (t1 - t0), (b[2] - a[2]) / (t1 - t0)];
+        last_v = v;
+        if t1 > 0.0 && t0 < horizon {
+            out.push(Piece { t0, t1, p: a, v });
+        }
+    }
+    if let Some(last) = s.last() {
+        let tl = secs(last.get_time());
+        if extend && tl < horizon {
+            out.push(Piece { t0: tl, t1: horizon, p: last.get_coordinates(), v: last_v });
+        }
+    }
+    out
+}
+/*-----------------------------------------------------------
+  intervals [s, e] on which both objects move in a straight
+  line, with relative position at s and relative velocity,
+  limited to [lo, hi]
+*/
+fn relative(a: &[Piece], b: &[Piece], lo: f64, hi: f64) -> Vec<(f64, f64, [f64; 3], [f64; 3])> {
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let s = a[i].t0.max(b[j].t0).max(lo);
+        let e = a[i].t1.min(b[j].t1).min(hi);
+        if s < e {
+            let (pa, pb) = (a[i].at(s), b[j].at(s));
+            let r = [pb[0] - pa[0], pb[1] - pa[1], pb[2] - pa[2]];
+            let rv = [b[j].v[0] - a[i].v[0], b[j].v[1] - a[i].v[1], b[j].v[2] - a[i].v[2]];
+            out.push((s, e, r, rv));
+        }
+        if a[i].t1 < b[j].t1 { i += 1; } else { j += 1; }
+    }
+    out
+}
+fn at_secs(origin: DateTime<Utc>, s: f64) -> DateTime<Utc> {
+    origin + Duration::nanoseconds((s * 1e9).round() as i64)
+}
+fn approach_at(origin: DateTime<Utc>, s: f64, r: &[f64; 3]) -> Approach {
+    let horizontal = (r[0] * r[0] + r[1] * r[1]).sqrt();
+    Approach {
+        time: at_secs(origin, s),
+        distance: (r[0] * r[0] + r[1] * r[1] + r[2] * r[2]).sqrt(),
+        horizontal,
+        vertical: r[2].abs(),
+    }
+}
+/*-- minimum 3-D separation over the relative intervals --*/
+fn minimum(origin: DateTime<Utc>, rel: &[(f64, f64, [f64; 3], [f64; 3])]) -> Option<Approach> {
+    let mut best: Option<Approach> = None;
+    for (s, e, r, rv) in rel {
+        let vv = rv[0] * rv[0] + rv[1] * rv[1] + rv[2] * rv[2];
+        let rdv = r[0] * rv[0] + r[1] * rv[1] + r[2] * rv[2];
+        let tau = if vv > 0.0 { (-rdv / vv).clamp(0.0, e - s) } else { 0.0 };
+        let rt = [r[0] + rv[0] * tau, r[1] + rv[1] * tau, r[2] + rv[2] * tau];
+        let cand = approach_at(origin, s + tau, &rt);
+        if best.map_or(true, |b| cand.distance < b.distance) {
+            best = Some(cand);
+        }
+    }
+    best
+}
+
+/*-----------------------------------------------------------
+  time and distance of closest approach of a and b
+  - None if their time spans don't overlap in an interval
+*/
+pub fn closest_approach(a: &Trajectory<f64>, b: &Trajectory<f64>) -> Option<Approach> {
+    let (sa, sb) = (a.start_time()?, b.start_time()?);
+    let (ea, eb) = (a.end_time()?, b.end_time()?);
+    let origin = sa.max(sb);
+    let end = ea.min(eb);
+    if end < origin {
+        return None;
+    }
+    let (pa, pb) = (pieces(a, origin, end, false), pieces(b, origin, end, false));
+    minimum(origin, &relative(&pa, &pb, 0.0, seconds(end - origin)))
+}
+
+/*-- loss of separation between two tracks over [start, end] --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub a: String,
+    pub b: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /*-- closest approach during the episode --*/
+    pub closest: Approach,
+}
+
+/*-- define ConflictDetector type --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictDetector {
+    horizontal: f64,
+    vertical: f64,
+    look_ahead: Duration,
+    extrapolate: bool,
+}
+impl ConflictDetector {
+    /*-- minima in coordinate units, look_ahead measured from now --*/
+    pub fn new(horizontal: f64, vertical: f64, look_ahead: Duration) -> ConflictDetector {
+        ConflictDetector { horizontal, vertical, look_ahead, extrapolate: true }
+    }
+    /*-- false: tracks are checked only where they have samples --*/
+    pub fn with_extrapolation(mut self, extrapolate: bool) -> ConflictDetector {
+        self.extrapolate = extrapolate;
+        self
+    }
+    /*-----------------------------------------------------------
+      every conflict between pairs of named tracks in
+      [now, now + look_ahead], ordered by pair, then by start
+    */
+    pub fn detect(&self, now: DateTime<Utc>, tracks: &[(&str, &Trajectory<f64>)]) -> Vec<Conflict> {
+        let end = now + self.look_ahead;
+        let horizon = seconds(self.look_ahead);
+        let all: Vec<Vec<Piece>> = tracks.iter()
+            .map(|(_, tr)| pieces(tr, now, end, self.extrapolate))
+            .collect();
+        let mut out = Vec::new();
+        for i in 0..tracks.len() {
+            for j in (i + 1)..tracks.len() {
+                let rel = relative(&all[i], &all[j], 0.0, horizon);
+                for (s, e) in self.episodes(&rel) {
+                    let within: Vec<_> = rel.iter()
+                        .filter(|(rs, re, _, _)| *re > s && *rs < e)
+                        .map(|&(rs, re, r, rv)| {
+                            let lo = rs.max(s);
+                            let d = lo - rs;
+                            (lo, re.min(e), [r[0] + rv[0] * d, r[1] + rv[1] * d, r[2] + rv[2] * d], rv)
+                        })
+                        .collect();
+                    if let Some(closest) = minimum(now, &within) {
+                        out.push(Conflict {
+                            a: tracks[i].0.to_string(),
+                            b: tracks[j].0.to_string(),
+                            start: at_secs(now, s),
+                            end: at_secs(now, e),
+                            closest,
+                        });
+                    }
+                }
+            }
+        }
+        out
+    }
+    /*-- merged intervals where both minima are violated --*/
+    fn episodes(&self, rel: &[(f64, f64, [f64; 3], [f64; 3])]) -> Vec<(f64, f64)> {
+        let mut out: Vec<(f64, f64)> = Vec::new();
+        for (s, e, r, rv) in rel {
+            let len = e - s;
+            let h = inside_quadratic(
+                rv[0] * rv[0] + rv[1] * rv[1],
+                2.0 * (r[0] * rv[0] + r[1] * rv[1]),
+                r[0] * r[0] + r[1] * r[1] - self.horizontal * self.horizontal,
+                len,
+            );
+            let v = if rv[2] == 0.0 {
+                if r[2].abs() < self.vertical { Some((0.0, len)) } else { None }
+            } else {
+                let (x, y) = ((-self.vertical - r[2]) / rv[2], (self.vertical - r[2]) / rv[2]);
+                Some((x.min(y), x.max(y)))
+            };
+            if let (Some(h), Some(v)) = (h, v) {
+                let (lo, hi) = (h.0.max(v.0).max(0.0), h.1.min(v.1).min(len));
+                if lo >= hi {
+                    continue;
+                }
+                match out.last_mut() {
+                    Some(last) if (last.1 - (s + lo)).abs() < 1e-9 => last.1 = s + hi,
+                    _ => out.push((s + lo, s + hi)),
+                }
+            }
+        }
+        out
+    }
+}
+/*-- where a t^2 + b t + c < 0, within [0, len] --*/
+fn inside_quadratic(a: f64, b: f64, c: f64, len: f64) -> Option<(f64, f64)> {
+    if a == 0.0 {
+        return if b == 0.0 {
+            if c < 0.0 { Some((0.0, len)) } else { None }
+        } else if b > 0.0 {
+            Some((f64::NEG_INFINITY, -c / b))
+        } else {
+            Some((-c / b, f64::INFINITY))
+        };
+    }
+    let disc = b * b - 4.0 * a * c;
+    if disc <= 0.0 {
+        return None;
+    }
+    let sq = disc.sqrt();
+    Some(((-b - sq) / (2.0 * a), (-b + sq) / (2.0 * a)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+    fn track(t0: DateTime<Utc>, pts: &[(i64, [f64; 3])]) -> Trajectory<f64> {
+        Trajectory::from_points(pts.iter().map(|(s, c)| {
+            let mut p = Point::new();
+            p.set_coordinates(c);
+            p.set_time(t0 + Duration::seconds(*s));
+            p
+        }).collect())
+    }
+    #[test]
+    fn crossing_tracks() {
+        let t0 = Utc::now();
+        /*-- a flies east, b flies north, 3 units above --*/
+        let a = track(t0, &[(0, [-10.0, 0.0, 0.0]), (20, [10.0, 0.0, 0.0])]);
+        let b = track(t0, &[(0, [0.0, -12.0, 3.0]), (10, [0.0, -2.0, 3.0]), (20, [0.0, 8.0, 3.0])]);
+        let cpa = closest_approach(&a, &b).unwrap();
+        /*-- relative position (10 - t, t - 12, 3), minimum at t = 11 --*/
+        assert_eq!(cpa.time, t0 + Duration::seconds(11));
+        assert!((cpa.horizontal - 2.0f64.sqrt()).abs() < 1e-9);
+        assert_eq!(cpa.vertical, 3.0);
+        assert!((cpa.distance - 11.0f64.sqrt()).abs() < 1e-9);
+        let late = track(t0, &[(30, [0.0; 3]), (40, [0.0; 3])]);
+        assert!(closest_approach(&a, &late).is_none());
+    }
+    #[test]
+    fn conflicts_in_window() {
+        let t0 = Utc::now();
+        let a = track(t0, &[(0, [0.0, 0.0, 100.0]), (10, [100.0, 0.0, 100.0])]);
+        /*-- b heads straight at a's path, extrapolated past its last sample --*/
+        let b = track(t0, &[(0, [200.0, 0.0, 120.0]), (5, [150.0, 0.0, 120.0])]);
+        let c = track(t0, &[(0, [0.0, 0.0, 500.0]), (10, [100.0, 0.0, 500.0])]);
+        let tracks = [("a", &a), ("b", &b), ("c", &c)];
+        let det = ConflictDetector::new(20.0, 50.0, Duration::seconds(30));
+        let found = det.detect(t0, &tracks);
+        assert_eq!(found.len(), 1);
+        let k = &found[0];
+        assert_eq!((k.a.as_str(), k.b.as_str()), ("a", "b"));
+        /*-- a extrapolated at 10/s, b at -10/s, gap 200 - 20t < 20 for t > 9 --*/
+        assert_eq!(k.start, t0 + Duration::seconds(9));
+        assert_eq!(k.end, t0 + Duration::seconds(11));
+        assert_eq!(k.closest.time, t0 + Duration::seconds(10));
+        assert!(k.closest.horizontal < 1e-9);
+        assert!(det.clone().with_extrapolation(false).detect(t0, &tracks).is_empty());
+        assert!(ConflictDetector::new(20.0, 10.0, Duration::seconds(30)).detect(t0, &tracks).is_empty());
+        assert!(ConflictDetector::new(20.0, 50.0, Duration::seconds(5)).detect(t0, &tracks).is_empty());
+    }
+}
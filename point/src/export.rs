@@ -0,0 +1,322 @@
+/////////////////////////////////////////////////////////////
+// point::export.rs - GeoJSON and KML export               //
+/////////////////////////////////////////////////////////////
+/*
+   Writes points and tracks in formats GIS viewers open
+   directly:
+   - GeoJson: a FeatureCollection of Point and LineString
+     Features (RFC 7946)
+   - Kml:     a Document of Placemarks holding a Point or a
+     LineString (OGC KML 2.2)
+   Each point's name and RFC-3339 time go in the Feature
+   properties, or in the Placemark name and TimeStamp.  A
+   track keeps its start and end times, and GeoJSON tracks
+   also list every sample time in a "times" property.
+
+   Positions are written as longitude, latitude, altitude.
+   GeoPoint supplies them from its lat, lon, and alt.  A
+   Point<f64> is written as is, so it must hold x = longitude,
+   y = latitude, z = altitude.  Other types implement GeoExport.
+   Neither format has NaN or infinity, so a point whose
+   position isn't finite is skipped, and counted in skipped().
+   A track keeps its finite samples.  A LineString needs two
+   positions, so a track with fewer finite samples is skipped
+   and all its samples are counted.
+*/
+use super::point_io::json_string;
+use super::{GeoPoint, Named, Point, SpaceTime};
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+/*-- objects that can be placed on a map --*/
+pub trait GeoExport: SpaceTime<f64> + Named {
+    /*-- degrees, degrees, meters --*/
+    fn lon_lat_alt(&self) -> [f64; 3];
+}
+impl GeoExport for Point<f64> {
+    fn lon_lat_alt(&self) -> [f64; 3] {
+        self.get_coordinates()
+    }
+}
+impl GeoExport for GeoPoint {
+    fn lon_lat_alt(&self) -> [f64; 3] {
+        [self.lon(), self.lat(), self.alt()]
+    }
+}
+
+/*-- position, None unless every coordinate is finite --*/
+fn finite_position<P: GeoExport + ?Sized>(pt: &P) -> Option<[f64; 3]> {
+    let c = pt.lon_lat_alt();
+    if c.iter().all(|v| v.is_finite()) { Some(c) } else { None }
+}
+/*-- track samples with finite positions --*/
+fn finite_samples<P: GeoExport>(pts: &[P]) -> Vec<(&P, [f64; 3])> {
+    pts.iter().filter_map(|p| finite_position(p).map(|c| (p, c))).collect()
+}
+fn json_position(c: &[f64; 3]) -> String {
+    format!("[{},{},{}]", c[0], c[1], c[2])
+}
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/*-- define GeoJson type, a FeatureCollection being built --*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeoJson {
+    features: Vec<String>,
+    skipped: usize,
+}
+impl GeoJson {
+    pub fn new() -> GeoJson {
+        GeoJson::default()
+    }
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+    /*-- points and track samples left out for non-finite positions --*/
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+    /*-- one Point Feature, None if the position isn't finite --*/
+    pub fn feature<P: GeoExport + ?Sized>(pt: &P) -> Option<String> {
+        Some(format!(
+            "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"Point\",\"coordinates\":{}}},\
+             \"properties\":{{\"name\":{},\"time\":{}}}}}",
+            json_position(&finite_position(pt)?),
+            json_string(pt.get_name()),
+            json_string(&pt.get_time_string())
+        ))
+    }
+    /*-- one LineString Feature, finite samples in track order, None if fewer than two --*/
+    pub fn track_feature<P: GeoExport>(name: &str, pts: &[P]) -> Option<String> {
+        let samples = finite_samples(pts);
+        if samples.len() < 2 {
+            return None;
+        }
+        let coords: Vec<String> = samples.iter().map(|(_, c)| json_position(c)).collect();
+        let times: Vec<String> = samples.iter().map(|(p, _)| json_string(&p.get_time_string())).collect();
+        Some(format!(
+            "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"LineString\",\"coordinates\":[{}]}},\
+             \"properties\":{{\"name\":{},\"start\":{},\"end\":{},\"times\":[{}]}}}}",
+            coords.join(","),
+            json_string(name),
+            times[0],
+            times[times.len() - 1],
+            times.join(",")
+        ))
+    }
+    pub fn add_point<P: GeoExport + ?Sized>(&mut self, pt: &P) -> &mut GeoJson {
+        match GeoJson::feature(pt) {
+            Some(f) => self.features.push(f),
+            None => self.skipped += 1,
+        }
+        self
+    }
+    pub fn add_points<P: GeoExport>(&mut self, pts: &[P]) -> &mut GeoJson {
+        for pt in pts {
+            self.add_point(pt);
+        }
+        self
+    }
+    pub fn add_track<P: GeoExport>(&mut self, name: &str, pts: &[P]) -> &mut GeoJson {
+        match GeoJson::track_feature(name, pts) {
+            Some(f) => {
+                self.skipped += pts.len() - finite_samples(pts).len();
+                self.features.push(f);
+            }
+            None => self.skipped += pts.len(),
+        }
+        self
+    }
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write!(out, "{}", self)
+    }
+}
+/*-- the FeatureCollection, one Feature per line --*/
+impl Display for GeoJson {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{\"type\":\"FeatureCollection\",\"features\":[")?;
+        for (i, feat) in self.features.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(f, "{}\n{}", sep, feat)?;
+        }
+        writeln!(f, "\n]}}")
+    }
+}
+
+/*-- define Kml type, a Document being built --*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Kml {
+    name: String,
+    placemarks: Vec<String>,
+    skipped: usize,
+}
+impl Kml {
+    pub fn new(name: &str) -> Kml {
+        Kml { name: name.to_string(), placemarks: Vec::new(), skipped: 0 }
+    }
+    pub fn len(&self) -> usize {
+        self.placemarks.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.placemarks.is_empty()
+    }
+    /*-- points and track samples left out for non-finite positions --*/
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+    fn kml_position(c: &[f64; 3]) -> String {
+        format!("{},{},{}", c[0], c[1], c[2])
+    }
+    /*-- one Placemark holding a Point, None if the position isn't finite --*/
+    pub fn placemark<P: GeoExport + ?Sized>(pt: &P) -> Option<String> {
+        Some(format!(
+            "  <Placemark>\n    <name>{}</name>\n    <TimeStamp><when>{}</when></TimeStamp>\n    \
+             <Point><altitudeMode>absolute</altitudeMode><coordinates>{}</coordinates></Point>\n  \
+             </Placemark>\n",
+            xml_escape(pt.get_name()),
+            pt.get_time_string(),
+            Kml::kml_position(&finite_position(pt)?)
+        ))
+    }
+    /*-- one Placemark holding a LineString of finite samples, with TimeSpan,
+         None if fewer than two --*/
+    pub fn track_placemark<P: GeoExport>(name: &str, pts: &[P]) -> Option<String> {
+        let samples = finite_samples(pts);
+        if samples.len() < 2 {
+            return None;
+        }
+        let coords: Vec<String> = samples.iter().map(|(_, c)| Kml::kml_position(c)).collect();
+        Some(format!(
+            "  <Placemark>\n    <name>{}</name>\n    \
+             <TimeSpan><begin>{}</begin><end>{}</end></TimeSpan>\n    \
+             <LineString><altitudeMode>absolute</altitudeMode><coordinates>{}</coordinates></LineString>\n  \
+             </Placemark>\n",
+            xml_escape(name),
+            samples[0].0.get_time_string(),
+            samples[samples.len() - 1].0.get_time_string(),
+            coords.join(" ")
+        ))
+    }
+    pub fn add_point<P: GeoExport + ?Sized>(&mut self, pt: &P) -> &mut Kml {
+        match Kml::placemark(pt) {
+            Some(p) => self.placemarks.push(p),
+            None => self.skipped += 1,
+        }
+        self
+    }
+    pub fn add_points<P: GeoExport>(&mut self, pts: &[P]) -> &mut Kml {
+        for pt in pts {
+            self.add_point(pt);
+        }
+        self
+    }
+    pub fn add_track<P: GeoExport>(&mut self, name: &str, pts: &[P]) -> &mut Kml {
+        match Kml::track_placemark(name, pts) {
+            Some(p) => {
+                self.skipped += pts.len() - finite_samples(pts).len();
+                self.placemarks.push(p);
+            }
+            None => self.skipped += pts.len(),
+        }
+        self
+    }
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write!(out, "{}", self)
+    }
+}
+impl Display for Kml {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(f, "<kml xmlns=\"http://www.opengis.net/kml/2.2\">")?;
+        writeln!(f, "<Document>\n  <name>{}</name>", xml_escape(&self.name))?;
+        for p in &self.placemarks {
+            f.write_str(p)?;
+        }
+        writeln!(f, "</Document>\n</kml>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    fn geo(lat: f64, lon: f64, secs: u32, name: &str) -> GeoPoint {
+        let mut g = GeoPoint::new(lat, lon, 100.0);
+        g.set_time(Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, secs).unwrap());
+        g.set_name(name);
+        g
+    }
+    #[test]
+    fn geojson_points_and_tracks() {
+        let a = geo(47.5, -122.25, 0, "SEA \"tower\"");
+        let b = geo(47.75, -122.5, 30, "b");
+        let f = GeoJson::feature(&a).unwrap();
+        assert!(f.contains("\"coordinates\":[-122.25,47.5,100]"));
+        assert!(f.contains("\"name\":\"SEA \\\"tower\\\"\""));
+        assert!(f.contains("\"time\":\"2026-10-18T12:00:00Z\""));
+        let mut doc = GeoJson::new();
+        doc.add_point(&a).add_track("flight 7", &[a.clone(), b.clone()]);
+        assert_eq!(doc.len(), 2);
+        let s = doc.to_string();
+        assert!(s.starts_with("{\"type\":\"FeatureCollection\",\"features\":["));
+        assert!(s.contains("\"type\":\"LineString\",\"coordinates\":[[-122.25,47.5,100],[-122.5,47.75,100]]"));
+        assert!(s.contains("\"start\":\"2026-10-18T12:00:00Z\",\"end\":\"2026-10-18T12:00:30Z\""));
+        let v: serde_json::Value = serde_json::from_str(&s).unwrap();
+        assert_eq!(v["features"][0]["properties"]["name"], "SEA \"tower\"");
+        assert!(GeoJson::track_feature::<GeoPoint>("empty", &[]).is_none());
+    }
+    #[test]
+    fn non_finite_skipped() {
+        let mut nan = Point::<f64>::new();
+        nan.set_coordinates(&[f64::NAN, 1.0, 2.0]);
+        let mut inf = nan.clone();
+        inf.set_coordinates(&[0.0, f64::INFINITY, 2.0]);
+        let ok = Point::<f64>::new();
+        let mut ok2 = ok.clone();
+        ok2.set_coordinates(&[1.0, 1.0, 0.0]);
+        assert!(GeoJson::feature(&nan).is_none() && Kml::placemark(&inf).is_none());
+        let mut doc = GeoJson::new();
+        doc.add_points(&[nan.clone(), ok.clone()])
+            .add_track("t", &[nan.clone(), ok.clone(), inf.clone(), ok2.clone()])
+            .add_track("one", &[ok.clone(), inf.clone()]);
+        assert_eq!((doc.len(), doc.skipped()), (2, 5));
+        let v: serde_json::Value = serde_json::from_str(&doc.to_string()).unwrap();
+        assert_eq!(v["features"][1]["geometry"]["coordinates"].as_array().unwrap().len(), 2);
+        let mut kml = Kml::new("k");
+        kml.add_point(&inf).add_track("t", &[ok.clone(), nan]).add_track("u", &[ok, ok2]);
+        assert_eq!((kml.len(), kml.skipped()), (1, 3));
+        assert!(!kml.to_string().contains("NaN") && !kml.to_string().contains("inf"));
+    }
+    #[test]
+    fn kml_document() {
+        let a = geo(47.5, -122.25, 0, "a & b");
+        let b = geo(47.75, -122.5, 30, "b");
+        let mut kml = Kml::new("flights");
+        kml.add_point(&a).add_track("flight <7>", &[a, b]);
+        let mut buf = Vec::new();
+        kml.write_to(&mut buf).unwrap();
+        let s = String::from_utf8(buf).unwrap();
+        assert!(s.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<kml"));
+        assert!(s.contains("<name>a &amp; b</name>"));
+        assert!(s.contains("<when>2026-10-18T12:00:00Z</when>"));
+        assert!(s.contains("<coordinates>-122.25,47.5,100 -122.5,47.75,100</coordinates>"));
+        assert!(s.contains("<name>flight &lt;7&gt;</name>"));
+        assert!(s.contains("<begin>2026-10-18T12:00:00Z</begin><end>2026-10-18T12:00:30Z</end>"));
+        assert!(s.trim_end().ends_with("</Document>\n</kml>"));
+    }
+}
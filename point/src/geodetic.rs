@@ -0,0 +1,285 @@
+/////////////////////////////////////////////////////////////
+// point::geodetic.rs - WGS-84 latitude, longitude, alt    //
+/////////////////////////////////////////////////////////////
+/*
+   GeoPoint is a position on the WGS-84 ellipsoid:
+   latitude and longitude in degrees, altitude in meters
+   above the ellipsoid.  It implements SpaceTime<f64> with
+   coordinates ordered [lat, lon, alt].
+   - to_ecef / from_ecef convert to and from Earth-Centered
+     Earth-Fixed x, y, z in meters.
+   - to_enu / from_enu convert to and from a local
+     East-North-Up frame with origin at another GeoPoint.
+   - great_circle_distance uses the haversine formula on a
+     sphere of mean Earth radius.
+   - vincenty_distance is the geodesic distance on the
+     ellipsoid, accurate to well under a millimeter.
+*/
+use super::{Point, SpaceTime};
+use chrono::offset::Utc;
+use chrono::DateTime;
+use std::fmt::*;
+
+/*-- WGS-84 ellipsoid --*/
+pub const WGS84_A: f64 = 6_378_137.0;
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+pub const WGS84_B: f64 = WGS84_A * (1.0 - WGS84_F);
+/*-- first eccentricity squared --*/
+pub const WGS84_E2: f64 = WGS84_F * (2.0 - WGS84_F);
+/*-- mean Earth radius, used by great-circle distance --*/
+pub const EARTH_MEAN_RADIUS: f64 = 6_371_008.8;
+
+/*-- define GeoPoint type --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoPoint {
+    lat: f64, lon: f64, alt: f64, t: DateTime<Utc>, n: String,
+}
+/*-- implement SpaceTime trait, coordinates are [lat, lon, alt] --*/
+impl SpaceTime<f64> for GeoPoint {
+    fn set_time(&mut self, st: DateTime<Utc>) {
+        self.t = st;
+    }
+    fn get_time(&self) -> DateTime<Utc> {
+        self.t
+    }
+    fn set_coordinates(&mut self, coor: &[f64; 3]) {
+        self.lat = coor[0];
+        self.lon = coor[1];
+        self.alt = coor[2];
+    }
+    fn get_coordinates(&self) -> [f64; 3] {
+        [self.lat, self.lon, self.alt]
+    }
+}
+/*-- implement Display trait --*/
+impl Display for GeoPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f, "{{ {:?}, {:?}, {:?}, {}, {:?} }}",
+            &self.lat, &self.lon, &self.alt,
+            &self.get_time_string(), &self.n
+        )
+    }
+}
+impl Default for GeoPoint {
+    fn default() -> GeoPoint {
+        GeoPoint::new(0.0, 0.0, 0.0)
+    }
+}
+/*-- implement GeoPoint methods --*/
+impl GeoPoint {
+    /*-- degrees, degrees, meters --*/
+    pub fn new(lat: f64, lon: f64, alt: f64) -> GeoPoint {
+        GeoPoint { lat, lon, alt, t: Utc::now(), n: String::default() }
+    }
+    pub fn lat(&self) -> f64 {
+        self.lat
+    }
+    pub fn lon(&self) -> f64 {
+        self.lon
+    }
+    pub fn alt(&self) -> f64 {
+        self.alt
+    }
+    pub fn set_name(&mut self, name: &str) {
+        self.n = name.to_string();
+    }
+    pub fn get_name(&self) -> &str {
+        &self.n
+    }
+    /*-- Earth-Centered Earth-Fixed x, y, z in meters --*/
+    pub fn to_ecef(&self) -> [f64; 3] {
+        let (phi, lam) = (self.lat.to_radians(), self.lon.to_radians());
+        let (sp, cp) = phi.sin_cos();
+        let (sl, cl) = lam.sin_cos();
+        /*-- prime vertical radius of curvature --*/
+        let nr = WGS84_A / (1.0 - WGS84_E2 * sp * sp).sqrt();
+        [
+            (nr + self.alt) * cp * cl,
+            (nr + self.alt) * cp * sl,
+            (nr * (1.0 - WGS84_E2) + self.alt) * sp,
+        ]
+    }
+    /*-----------------------------------------------------
+      geodetic position of ECEF x, y, z
+      - Heikkinen's closed form, no iteration needed
+    */
+    pub fn from_ecef(ecef: &[f64; 3]) -> GeoPoint {
+        let [x, y, z] = *ecef;
+        let (a, b, e2) = (WGS84_A, WGS84_B, WGS84_E2);
+        let p = (x * x + y * y).sqrt();
+        let lon = y.atan2(x).to_degrees();
+        if p < 1e-9 {
+            /*-- on the polar axis --*/
+            let lat = if z >= 0.0 { 90.0 } else { -90.0 };
+            return GeoPoint::new(lat, 0.0, z.abs() - b);
+        }
+        let ep2 = (a * a - b * b) / (b * b);
+        let ff = 54.0 * b * b * z * z;
+        let g = p * p + (1.0 - e2) * z * z - e2 * (a * a - b * b);
+        let c = e2 * e2 * ff * p * p / (g * g * g);
+        let s = (1.0 + c + (c * c + 2.0 * c).sqrt()).cbrt();
+        let k = s + 1.0 + 1.0 / s;
+        let pp = ff / (3.0 * k * k * g * g);
+        let q = (1.0 + 2.0 * e2 * e2 * pp).sqrt();
+        let r0 = -pp * e2 * p / (1.0 + q)
+            + (0.5 * a * a * (1.0 + 1.0 / q)
+                - pp * (1.0 - e2) * z * z / (q * (1.0 + q))
+                - 0.5 * pp * p * p)
+                .sqrt();
+        let u = ((p - e2 * r0).powi(2) + z * z).sqrt();
+        let v = ((p - e2 * r0).powi(2) + (1.0 - e2) * z * z).sqrt();
+        let z0 = b * b * z / (a * v);
+        let alt = u * (1.0 - b * b / (a * v));
+        let lat = ((z + ep2 * z0) / p).atan().to_degrees();
+        GeoPoint::new(lat, lon, alt)
+    }
+    /*-- east, north, up in meters, relative to origin --*/
+    pub fn to_enu(&self, origin: &GeoPoint) -> [f64; 3] {
+        let p = self.to_ecef();
+        let o = origin.to_ecef();
+        let d = [p[0] - o[0], p[1] - o[1], p[2] - o[2]];
+        let (sp, cp) = origin.lat.to_radians().sin_cos();
+        let (sl, cl) = origin.lon.to_radians().sin_cos();
+        [
+            -sl * d[0] + cl * d[1],
+            -sp * cl * d[0] - sp * sl * d[1] + cp * d[2],
+            cp * cl * d[0] + cp * sl * d[1] + sp * d[2],
+        ]
+    }
+    /*-- inverse of to_enu, keeps origin's time and name --*/
+    pub fn from_enu(enu: &[f64; 3], origin: &GeoPoint) -> GeoPoint {
+        let [e, n, u] = *enu;
+        let (sp, cp) = origin.lat.to_radians().sin_cos();
+        let (sl, cl) = origin.lon.to_radians().sin_cos();
+        let o = origin.to_ecef();
+        let ecef = [
+            o[0] - sl * e - sp * cl * n + cp * cl * u,
+            o[1] + cl * e - sp * sl * n + cp * sl * u,
+            o[2] + cp * n + sp * u,
+        ];
+        let mut gp = GeoPoint::from_ecef(&ecef);
+        gp.t = origin.t;
+        gp.n = origin.n.clone();
+        gp
+    }
+    /*-- ECEF position as a Point, keeping time and name --*/
+    pub fn to_ecef_point(&self) -> Point<f64> {
+        let [x, y, z] = self.to_ecef();
+        Point { x, y, z, t: self.t, n: self.n.clone() }
+    }
+    /*-- ENU position as a Point, keeping time and name --*/
+    pub fn to_enu_point(&self, origin: &GeoPoint) -> Point<f64> {
+        let [x, y, z] = self.to_enu(origin);
+        Point { x, y, z, t: self.t, n: self.n.clone() }
+    }
+    /*-- haversine distance in meters on the mean sphere, ignores altitude --*/
+    pub fn great_circle_distance(&self, other: &GeoPoint) -> f64 {
+        let (p1, p2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dp = p2 - p1;
+        let dl = (other.lon - self.lon).to_radians();
+        let h = (dp / 2.0).sin().powi(2) + p1.cos() * p2.cos() * (dl / 2.0).sin().powi(2);
+        2.0 * EARTH_MEAN_RADIUS * h.sqrt().min(1.0).asin()
+    }
+    /*-----------------------------------------------------
+      geodesic distance in meters on the WGS-84 ellipsoid
+      - Vincenty's inverse method, ignores altitude
+      - None if the iteration does not converge, which
+        happens only for nearly antipodal points
+    */
+    pub fn vincenty_distance(&self, other: &GeoPoint) -> Option<f64> {
+        let (a, b, f) = (WGS84_A, WGS84_B, WGS84_F);
+        let l = (other.lon - self.lon).to_radians();
+        let u1 = ((1.0 - f) * self.lat.to_radians().tan()).atan();
+        let u2 = ((1.0 - f) * other.lat.to_radians().tan()).atan();
+        let (su1, cu1) = u1.sin_cos();
+        let (su2, cu2) = u2.sin_cos();
+        let mut lam = l;
+        for _ in 0..200 {
+            let (sl, cl) = lam.sin_cos();
+            let sin_sigma = ((cu2 * sl).powi(2) + (cu1 * su2 - su1 * cu2 * cl).powi(2)).sqrt();
+            if sin_sigma == 0.0 {
+                return Some(0.0);
+            }
+            let cos_sigma = su1 * su2 + cu1 * cu2 * cl;
+            let sigma = sin_sigma.atan2(cos_sigma);
+            let sin_alpha = cu1 * cu2 * sl / sin_sigma;
+            let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+            /*-- equatorial line has cos2_alpha == 0 --*/
+            let cos_2sm = if cos2_alpha == 0.0 {
+                0.0
+            } else {
+                cos_sigma - 2.0 * su1 * su2 / cos2_alpha
+            };
+            let c = f / 16.0 * cos2_alpha * (4.0 + f * (4.0 - 3.0 * cos2_alpha));
+            let prev = lam;
+            lam = l + (1.0 - c) * f * sin_alpha
+                * (sigma + c * sin_sigma
+                    * (cos_2sm + c * cos_sigma * (-1.0 + 2.0 * cos_2sm * cos_2sm)));
+            if (lam - prev).abs() < 1e-12 {
+                let u_sq = cos2_alpha * (a * a - b * b) / (b * b);
+                let aa = 1.0 + u_sq / 16384.0
+                    * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+                let bb = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+                let delta_sigma = bb * sin_sigma
+                    * (cos_2sm + bb / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2sm * cos_2sm)
+                            - bb / 6.0 * cos_2sm
+                                * (-3.0 + 4.0 * sin_sigma * sin_sigma)
+                                * (-3.0 + 4.0 * cos_2sm * cos_2sm)));
+                return Some(b * aa * (sigma - delta_sigma));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() <= tol
+    }
+    #[test]
+    fn ecef_round_trip() {
+        for &(lat, lon, alt) in &[
+            (0.0, 0.0, 0.0), (42.0, -76.0, 150.0), (-33.9, 151.2, 10_000.0),
+            (89.9, 45.0, -20.0), (90.0, 0.0, 100.0),
+        ] {
+            let gp = GeoPoint::new(lat, lon, alt);
+            let rt = GeoPoint::from_ecef(&gp.to_ecef());
+            assert!(close(rt.lat(), lat, 1e-9), "lat {} vs {}", rt.lat(), lat);
+            assert!(close(rt.alt(), alt, 1e-5), "alt {} vs {}", rt.alt(), alt);
+            if lat.abs() < 90.0 {
+                assert!(close(rt.lon(), lon, 1e-9));
+            }
+        }
+        let eq = GeoPoint::new(0.0, 0.0, 0.0).to_ecef();
+        assert!(close(eq[0], WGS84_A, 1e-6));
+    }
+    #[test]
+    fn enu_frame() {
+        let origin = GeoPoint::new(43.0, -76.1, 120.0);
+        let up = GeoPoint::new(43.0, -76.1, 220.0);
+        let enu = up.to_enu(&origin);
+        assert!(close(enu[0], 0.0, 1e-6) && close(enu[1], 0.0, 1e-6) && close(enu[2], 100.0, 1e-6));
+        let north = GeoPoint::new(43.001, -76.1, 120.0).to_enu(&origin);
+        assert!(north[1] > 100.0 && close(north[0], 0.0, 1e-6));
+        let rt = GeoPoint::from_enu(&[250.0, -125.0, 30.0], &origin);
+        let back = rt.to_enu(&origin);
+        assert!(close(back[0], 250.0, 1e-6) && close(back[1], -125.0, 1e-6) && close(back[2], 30.0, 1e-6));
+    }
+    #[test]
+    fn distances() {
+        /*-- Flinders Peak to Buninyong, Vincenty's published example --*/
+        let a = GeoPoint::new(-37.951_033_417, 144.424_867_889, 0.0);
+        let b = GeoPoint::new(-37.652_821_139, 143.926_495_528, 0.0);
+        assert!(close(a.vincenty_distance(&b).unwrap(), 54_972.271, 1e-3));
+        assert!(close(a.great_circle_distance(&b), 54_972.271, 200.0));
+        /*-- one degree of arc along the equator --*/
+        let e0 = GeoPoint::new(0.0, 0.0, 0.0);
+        let e1 = GeoPoint::new(0.0, 1.0, 0.0);
+        assert!(close(e0.vincenty_distance(&e1).unwrap(), 111_319.491, 1e-3));
+        assert_eq!(e0.vincenty_distance(&e0), Some(0.0));
+    }
+}
@@ -0,0 +1,294 @@
+/////////////////////////////////////////////////////////////
+// point::geofence.rs - Zones and enter/exit monitoring    //
+/////////////////////////////////////////////////////////////
+/*
+   Zone<T> is a named region of space:
+   - Polygon:  2-D polygon in x, y with altitude floor and
+               ceiling on z
+   - Cylinder: circle in x, y with floor and ceiling
+   - Sphere:   ball around a 3-D center
+   Boundaries count as inside.
+
+   Geofence<T> watches a stream of SpaceTime objects, keyed by
+   name, and reports FenceEvents:
+   - Enter: first observation inside a zone
+   - Exit:  first observation outside after being inside
+   - Dwell: inside continuously for the dwell time, reported
+            once per visit
+   An object's observations must be in time order, older ones
+   are ignored.  Objects supply their names with the Named
+   trait, or the caller passes a name to observe_as.
+*/
+use super::{GeoPoint, Point, SpaceTime};
+use chrono::{DateTime, Duration, Utc};
+use num_traits::Float;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/*-- key used to tell objects apart in a stream --*/
+pub trait Named {
+    fn get_name(&self) -> &str;
+}
+impl<T> Named for Point<T> where T: Default + Debug + Clone {
+    fn get_name(&self) -> &str {
+        Point::get_name(self)
+    }
+}
+impl Named for GeoPoint {
+    fn get_name(&self) -> &str {
+        GeoPoint::get_name(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape<T> {
+    Polygon { vertices: Vec<[T; 2]>, floor: T, ceiling: T },
+    Cylinder { center: [T; 2], radius: T, floor: T, ceiling: T },
+    Sphere { center: [T; 3], radius: T },
+}
+/*-- define Zone<T> type --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone<T> {
+    name: String,
+    shape: Shape<T>,
+}
+impl<T: Float> Zone<T> {
+    pub fn new(name: &str, shape: Shape<T>) -> Zone<T> {
+        Zone { name: name.to_string(), shape }
+    }
+    pub fn polygon(name: &str, vertices: &[[T; 2]], floor: T, ceiling: T) -> Zone<T> {
+        Zone::new(name, Shape::Polygon { vertices: vertices.to_vec(), floor, ceiling })
+    }
+    pub fn cylinder(name: &str, center: [T; 2], radius: T, floor: T, ceiling: T) -> Zone<T> {
+        Zone::new(name, Shape::Cylinder { center, radius, floor, ceiling })
+    }
+    pub fn sphere(name: &str, center: [T; 3], radius: T) -> Zone<T> {
+        Zone::new(name, Shape::Sphere { center, radius })
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn shape(&self) -> &Shape<T> {
+        &self.shape
+    }
+    pub fn contains(&self, c: &[T; 3]) -> bool {
+        match &self.shape {
+            Shape::Polygon { vertices, floor, ceiling } => {
+                *floor <= c[2] && c[2] <= *ceiling && in_polygon(vertices, c[0], c[1])
+            }
+            Shape::Cylinder { center, radius, floor, ceiling } => {
+                let (dx, dy) = (c[0] - center[0], c[1] - center[1]);
+                *floor <= c[2] && c[2] <= *ceiling && dx * dx + dy * dy <= *radius * *radius
+            }
+            Shape::Sphere { center, radius } => {
+                let d2 = (0..3).fold(T::zero(), |acc, k| acc + (c[k] - center[k]) * (c[k] - center[k]));
+                d2 <= *radius * *radius
+            }
+        }
+    }
+    pub fn contains_point<S>(&self, st: &S) -> bool
+    where S: SpaceTime<T> + ?Sized {
+        self.contains(&st.get_coordinates())
+    }
+}
+/*-- even-odd rule, with points on an edge counted as inside --*/
+fn in_polygon<T: Float>(v: &[[T; 2]], x: T, y: T) -> bool {
+    if v.len() < 3 {
+        return false;
+    }
+    let mut inside = false;
+    let mut j = v.len() - 1;
+    for i in 0..v.len() {
+        let ([xi, yi], [xj, yj]) = (v[i], v[j]);
+        let cross = (xj - xi) * (y - yi) - (yj - yi) * (x - xi);
+        let within = x >= xi.min(xj) && x <= xi.max(xj) && y >= yi.min(yj) && y <= yi.max(yj);
+        if cross == T::zero() && within {
+            return true;
+        }
+        if (yi > y) != (yj > y) && x < xi + (y - yi) * (xj - xi) / (yj - yi) {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenceEventKind {
+    Enter,
+    Exit,
+    Dwell,
+}
+/*-- one zone transition of one object --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct FenceEvent {
+    pub kind: FenceEventKind,
+    pub object: String,
+    pub zone: String,
+    pub time: DateTime<Utc>,
+    /*-- time the current, or just ended, visit began --*/
+    pub entered: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct Visit {
+    entered: DateTime<Utc>,
+    dwell_sent: bool,
+}
+#[derive(Debug, Clone, Default)]
+struct Track {
+    last: Option<DateTime<Utc>>,
+    /*-- zone index -> visit in progress --*/
+    visits: HashMap<usize, Visit>,
+}
+
+/*-- define Geofence<T> monitor --*/
+#[derive(Debug, Clone)]
+pub struct Geofence<T> {
+    zones: Vec<Zone<T>>,
+    dwell: Option<Duration>,
+    objects: HashMap<String, Track>,
+}
+impl<T: Float> Default for Geofence<T> {
+    fn default() -> Geofence<T> {
+        Geofence::new()
+    }
+}
+impl<T: Float> Geofence<T> {
+    pub fn new() -> Geofence<T> {
+        Geofence { zones: Vec::new(), dwell: None, objects: HashMap::new() }
+    }
+    pub fn with_zone(mut self, zone: Zone<T>) -> Geofence<T> {
+        self.add_zone(zone);
+        self
+    }
+    /*-- without a dwell time no Dwell events are reported --*/
+    pub fn with_dwell(mut self, dwell: Duration) -> Geofence<T> {
+        self.dwell = Some(dwell);
+        self
+    }
+    pub fn add_zone(&mut self, zone: Zone<T>) {
+        self.zones.push(zone);
+    }
+    pub fn zones(&self) -> &[Zone<T>] {
+        &self.zones
+    }
+    /*-- names of zones the object is in, in zone order --*/
+    pub fn zones_of(&self, object: &str) -> Vec<&str> {
+        let mut idx: Vec<usize> = match self.objects.get(object) {
+            Some(t) => t.visits.keys().copied().collect(),
+            None => return Vec::new(),
+        };
+        idx.sort_unstable();
+        idx.into_iter().map(|i| self.zones[i].name()).collect()
+    }
+    /*-- stop tracking an object, no events are reported --*/
+    pub fn forget(&mut self, object: &str) {
+        self.objects.remove(object);
+    }
+    pub fn observe<S>(&mut self, st: &S) -> Vec<FenceEvent>
+    where S: SpaceTime<T> + Named + ?Sized {
+        self.observe_as(st.get_name(), st)
+    }
+    /*-----------------------------------------------------------
+      process one observation of object, returns exits, then
+      enters, then dwells, each in zone order
+    */
+    pub fn observe_as<S>(&mut self, object: &str, st: &S) -> Vec<FenceEvent>
+    where S: SpaceTime<T> + ?Sized {
+        let (t, c) = (st.get_time(), st.get_coordinates());
+        let track = self.objects.entry(object.to_string()).or_default();
+        if track.last.is_some_and(|last| t < last) {
+            return Vec::new();
+        }
+        track.last = Some(t);
+        let event = |kind, zone: &Zone<T>, entered| FenceEvent {
+            kind, object: object.to_string(), zone: zone.name.clone(), time: t, entered,
+        };
+        let (mut exits, mut enters, mut dwells) = (Vec::new(), Vec::new(), Vec::new());
+        for (i, zone) in self.zones.iter().enumerate() {
+            let inside = zone.contains(&c);
+            match (inside, track.visits.get_mut(&i)) {
+                (true, None) => {
+                    track.visits.insert(i, Visit { entered: t, dwell_sent: false });
+                    enters.push(event(FenceEventKind::Enter, zone, t));
+                }
+                (false, Some(v)) => {
+                    exits.push(event(FenceEventKind::Exit, zone, v.entered));
+                    track.visits.remove(&i);
+                }
+                _ => {}
+            }
+            if let (Some(dwell), Some(v)) = (self.dwell, track.visits.get_mut(&i)) {
+                if !v.dwell_sent && t.signed_duration_since(v.entered) >= dwell {
+                    v.dwell_sent = true;
+                    dwells.push(event(FenceEventKind::Dwell, zone, v.entered));
+                }
+            }
+        }
+        exits.extend(enters);
+        exits.extend(dwells);
+        exits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn at(name: &str, c: [f64; 3], t0: DateTime<Utc>, secs: i64) -> Point<f64> {
+        let mut pt = Point::new();
+        pt.set_coordinates(&c);
+        pt.set_time(t0 + Duration::seconds(secs));
+        pt.set_name(name);
+        pt
+    }
+    #[test]
+    fn zone_containment() {
+        let l = Zone::polygon("berth", &[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [2.0, 2.0], [0.0, 4.0]], 0.0, 10.0);
+        assert!(l.contains(&[1.0, 1.0, 5.0]));
+        assert!(!l.contains(&[2.0, 3.0, 5.0]));
+        assert!(l.contains(&[4.0, 2.0, 0.0]));
+        assert!(!l.contains(&[1.0, 1.0, 11.0]));
+        let c = Zone::cylinder("tower", [0.0, 0.0], 5.0, 0.0, 100.0);
+        assert!(c.contains(&[3.0, 4.0, 50.0]));
+        assert!(!c.contains(&[3.0, 4.1, 50.0]));
+        let s = Zone::sphere("buoy", [0.0, 0.0, 0.0], 1.0);
+        assert!(s.contains_point(&at("x", [0.0, 0.6, 0.8], Utc::now(), 0)));
+        assert!(!Zone::polygon("line", &[[0.0, 0.0], [1.0, 1.0]], 0.0, 1.0).contains(&[0.5, 0.5, 0.5]));
+    }
+    #[test]
+    fn enter_dwell_exit() {
+        let t0 = Utc::now();
+        let mut fence = Geofence::new()
+            .with_zone(Zone::cylinder("harbor", [0.0, 0.0], 100.0, -10.0, 10.0))
+            .with_zone(Zone::sphere("dock", [50.0, 0.0, 0.0], 5.0))
+            .with_dwell(Duration::seconds(60));
+        assert!(fence.observe(&at("ship", [200.0, 0.0, 0.0], t0, 0)).is_empty());
+        let ev = fence.observe(&at("ship", [50.0, 0.0, 0.0], t0, 10));
+        assert_eq!(ev.iter().map(|e| e.zone.as_str()).collect::<Vec<_>>(), ["harbor", "dock"]);
+        assert!(ev.iter().all(|e| e.kind == FenceEventKind::Enter));
+        assert_eq!(fence.zones_of("ship"), ["harbor", "dock"]);
+        let ev = fence.observe(&at("ship", [20.0, 0.0, 0.0], t0, 70));
+        assert_eq!(ev.len(), 2);
+        assert_eq!((ev[0].kind, ev[0].zone.as_str()), (FenceEventKind::Exit, "dock"));
+        assert_eq!((ev[1].kind, ev[1].zone.as_str()), (FenceEventKind::Dwell, "harbor"));
+        assert_eq!(ev[1].entered, t0 + Duration::seconds(10));
+        assert!(fence.observe(&at("ship", [20.0, 0.0, 0.0], t0, 80)).is_empty());
+        /*-- older observation is ignored --*/
+        assert!(fence.observe(&at("ship", [500.0, 0.0, 0.0], t0, 75)).is_empty());
+        let ev = fence.observe(&at("ship", [500.0, 0.0, 0.0], t0, 90));
+        assert_eq!((ev[0].kind, ev[0].time), (FenceEventKind::Exit, t0 + Duration::seconds(90)));
+        assert!(fence.zones_of("ship").is_empty());
+    }
+    #[test]
+    fn objects_tracked_separately() {
+        let t0 = Utc::now();
+        let mut fence = Geofence::new().with_zone(Zone::sphere("z", [0.0; 3], 1.0));
+        assert_eq!(fence.observe(&at("a", [0.0; 3], t0, 0)).len(), 1);
+        assert_eq!(fence.observe(&at("b", [0.0; 3], t0, 0))[0].object, "b");
+        fence.forget("a");
+        assert!(fence.zones_of("a").is_empty());
+        let anon = at("", [5.0, 0.0, 0.0], t0, 1);
+        assert_eq!(fence.observe_as("b", &anon)[0].kind, FenceEventKind::Exit);
+    }
+}
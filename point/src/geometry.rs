@@ -0,0 +1,239 @@
+/////////////////////////////////////////////////////////////
+// point::geometry.rs - Vector operations for Point<T>     //
+/////////////////////////////////////////////////////////////
+/*
+   Treats the coordinates of a Point<T> as a 3-vector.
+   - Operators Add, Sub, Neg, and Mul<T> (scale) work on both
+     values and references.  The result carries the time and
+     name of the left operand.
+   - dot, manhattan_distance, distance_squared, and midpoint
+     work for any numeric T, unsigned included.
+   - cross needs signed T, its components can be negative.
+   - distance, norm, normalize, and lerp need floating point T.
+*/
+use super::Point;
+use num_traits::{Float, Num, Signed};
+use std::fmt::Debug;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/*-- build result from new coordinates, keeping time and name --*/
+pub(crate) fn with_coords<T>(pt: &Point<T>, x: T, y: T, z: T) -> Point<T>
+where T: Default + Debug {
+    Point { x, y, z, t: pt.t, n: pt.n.clone() }
+}
+/*-- |a - b| written so unsigned types never go negative --*/
+fn abs_diff<T: Num + PartialOrd>(a: T, b: T) -> T {
+    if a > b { a - b } else { b - a }
+}
+/*-- (a + b)/2 without forming a + b, integers truncate as (a + b)/2 would --*/
+fn half_sum<T: Num + PartialOrd + Clone>(a: T, b: T) -> T {
+    let two = T::one() + T::one();
+    let (ra, rb) = (a.clone() % two.clone(), b.clone() % two.clone());
+    let r = ra.clone() + rb.clone();
+    let h = r.clone() / two.clone();
+    let m = (a - ra) / two.clone() + (b - rb) / two.clone() + h.clone();
+    let left = r - h * two;
+    if m < T::zero() && left > T::zero() {
+        m + T::one()
+    } else if m > T::zero() && left < T::zero() {
+        m - T::one()
+    } else {
+        m
+    }
+}
+
+/*-- implement operators for references --*/
+impl<'a, T> Add for &'a Point<T>
+where T: Default + Debug + Clone + Add<Output = T> {
+    type Output = Point<T>;
+    fn add(self, rhs: &'a Point<T>) -> Point<T> {
+        with_coords(
+            self,
+            self.x.clone() + rhs.x.clone(),
+            self.y.clone() + rhs.y.clone(),
+            self.z.clone() + rhs.z.clone(),
+        )
+    }
+}
+impl<'a, T> Sub for &'a Point<T>
+where T: Default + Debug + Clone + Sub<Output = T> {
+    type Output = Point<T>;
+    fn sub(self, rhs: &'a Point<T>) -> Point<T> {
+        with_coords(
+            self,
+            self.x.clone() - rhs.x.clone(),
+            self.y.clone() - rhs.y.clone(),
+            self.z.clone() - rhs.z.clone(),
+        )
+    }
+}
+impl<T> Mul<T> for &Point<T>
+where T: Default + Debug + Clone + Mul<Output = T> {
+    type Output = Point<T>;
+    fn mul(self, s: T) -> Point<T> {
+        with_coords(
+            self,
+            self.x.clone() * s.clone(),
+            self.y.clone() * s.clone(),
+            self.z.clone() * s,
+        )
+    }
+}
+impl<T> Neg for &Point<T>
+where T: Default + Debug + Clone + Neg<Output = T> {
+    type Output = Point<T>;
+    fn neg(self) -> Point<T> {
+        with_coords(self, -self.x.clone(), -self.y.clone(), -self.z.clone())
+    }
+}
+/*-- operators for values forward to the reference versions --*/
+impl<T> Add for Point<T>
+where T: Default + Debug + Clone + Add<Output = T> {
+    type Output = Point<T>;
+    fn add(self, rhs: Point<T>) -> Point<T> {
+        &self + &rhs
+    }
+}
+impl<T> Sub for Point<T>
+where T: Default + Debug + Clone + Sub<Output = T> {
+    type Output = Point<T>;
+    fn sub(self, rhs: Point<T>) -> Point<T> {
+        &self - &rhs
+    }
+}
+impl<T> Mul<T> for Point<T>
+where T: Default + Debug + Clone + Mul<Output = T> {
+    type Output = Point<T>;
+    fn mul(self, s: T) -> Point<T> {
+        &self * s
+    }
+}
+impl<T> Neg for Point<T>
+where T: Default + Debug + Clone + Neg<Output = T> {
+    type Output = Point<T>;
+    fn neg(self) -> Point<T> {
+        -&self
+    }
+}
+
+/*-- operations for any numeric type --*/
+impl<T> Point<T> where T: Default + Debug + Clone + Num + PartialOrd {
+    /*-- x1*x2 + y1*y2 + z1*z2 --*/
+    pub fn dot(&self, other: &Point<T>) -> T {
+        self.x.clone() * other.x.clone()
+            + self.y.clone() * other.y.clone()
+            + self.z.clone() * other.z.clone()
+    }
+    /*-- square of Euclidean distance, no square root needed --*/
+    pub fn distance_squared(&self, other: &Point<T>) -> T {
+        let dx = abs_diff(self.x.clone(), other.x.clone());
+        let dy = abs_diff(self.y.clone(), other.y.clone());
+        let dz = abs_diff(self.z.clone(), other.z.clone());
+        dx.clone() * dx + dy.clone() * dy + dz.clone() * dz
+    }
+    /*-- sum of absolute coordinate differences --*/
+    pub fn manhattan_distance(&self, other: &Point<T>) -> T {
+        abs_diff(self.x.clone(), other.x.clone())
+            + abs_diff(self.y.clone(), other.y.clone())
+            + abs_diff(self.z.clone(), other.z.clone())
+    }
+    /*-- point half way between self and other --*/
+    pub fn midpoint(&self, other: &Point<T>) -> Point<T> {
+        with_coords(
+            self,
+            half_sum(self.x.clone(), other.x.clone()),
+            half_sum(self.y.clone(), other.y.clone()),
+            half_sum(self.z.clone(), other.z.clone()),
+        )
+    }
+}
+
+/*-- operations that need signed T --*/
+impl<T> Point<T> where T: Default + Debug + Clone + Signed {
+    /*-- vector perpendicular to self and other, right-handed --*/
+    pub fn cross(&self, other: &Point<T>) -> Point<T> {
+        let (ax, ay, az) = (self.x.clone(), self.y.clone(), self.z.clone());
+        let (bx, by, bz) = (other.x.clone(), other.y.clone(), other.z.clone());
+        with_coords(
+            self,
+            ay.clone() * bz.clone() - az.clone() * by.clone(),
+            az * bx.clone() - ax.clone() * bz,
+            ax * by - ay * bx,
+        )
+    }
+}
+
+/*-- operations that need floating point --*/
+impl<T> Point<T> where T: Default + Debug + Clone + Float {
+    /*-- length of coordinate vector --*/
+    pub fn norm(&self) -> T {
+        self.dot(self).sqrt()
+    }
+    /*-- Euclidean distance --*/
+    pub fn distance(&self, other: &Point<T>) -> T {
+        self.distance_squared(other).sqrt()
+    }
+    /*-- unit vector in same direction, None for zero vector --*/
+    pub fn normalize(&self) -> Option<Point<T>> {
+        let n = self.norm();
+        if n == T::zero() || !n.is_finite() {
+            return None;
+        }
+        Some(self * n.recip())
+    }
+    /*-- self at s = 0, other at s = 1, s is not clamped --*/
+    pub fn lerp(&self, other: &Point<T>, s: T) -> Point<T> {
+        self + &(&(other - self) * s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpaceTime;
+    fn pt<T>(c: [T; 3]) -> Point<T> where T: Default + Debug + Clone {
+        let mut p = Point::<T>::new();
+        p.set_coordinates(&c);
+        p
+    }
+    #[test]
+    fn operators() {
+        let mut a = pt([1, 2, 3]);
+        a.set_name("a");
+        let b = pt([4, 5, 6]);
+        assert_eq!((&a + &b).get_coordinates(), [5, 7, 9]);
+        assert_eq!((&b - &a).get_coordinates(), [3, 3, 3]);
+        assert_eq!((&a * 2).get_coordinates(), [2, 4, 6]);
+        assert_eq!((-&a).get_coordinates(), [-1, -2, -3]);
+        let c = a.clone() + b;
+        assert_eq!(c.get_name(), "a");
+        assert_eq!(c.get_time(), a.get_time());
+    }
+    #[test]
+    fn products_and_distances() {
+        let a = pt([1, 0, 0]);
+        let b = pt([0, 1, 0]);
+        assert_eq!(a.dot(&b), 0);
+        assert_eq!(a.cross(&b).get_coordinates(), [0, 0, 1]);
+        assert_eq!(a.manhattan_distance(&b), 2);
+        assert_eq!(pt([1u32, 5, 0]).distance_squared(&pt([4, 1, 0])), 25);
+        assert_eq!(pt([2u32, 4, 6]).midpoint(&pt([0, 0, 0])).get_coordinates(), [1, 2, 3]);
+        let p = pt([0.0, 3.0, 4.0]);
+        assert_eq!(p.norm(), 5.0);
+        assert_eq!(p.distance(&pt([0.0, 0.0, 0.0])), 5.0);
+        assert!((p.normalize().unwrap().norm() - 1.0).abs() < 1e-12);
+        assert!(pt([0.0, 0.0, 0.0]).normalize().is_none());
+        assert_eq!(p.lerp(&pt([0.0, 0.0, 0.0]), 0.5).get_coordinates(), [0.0, 1.5, 2.0]);
+    }
+    #[test]
+    fn midpoint_near_limits() {
+        let m = u32::MAX;
+        let c = pt([m, m, m - 1]).midpoint(&pt([m - 2, 1, m])).get_coordinates();
+        assert_eq!(c, [m - 1, m / 2 + 1, m - 1]);
+        let (lo, hi) = (i64::MIN, i64::MAX);
+        let c = pt([hi, lo, -3]).midpoint(&pt([hi - 1, hi, 0])).get_coordinates();
+        assert_eq!(c, [hi - 1, 0, -1]);
+        assert_eq!(pt([f64::MAX, 3.0, 0.0]).midpoint(&pt([f64::MAX, 0.0, -1.0])).get_coordinates(),
+                   [f64::MAX, 1.5, -0.5]);
+    }
+}
@@ -0,0 +1,396 @@
+/////////////////////////////////////////////////////////////
+// point::integer.rs - Overflow-safe and fixed-point math  //
+/////////////////////////////////////////////////////////////
+/*
+   Arithmetic on integer points that never panics:
+   - checked_*:    None if any coordinate overflows
+   - saturating_*: clamps at the type's min and max
+   - wrapping_*:   two's complement wraparound
+   for add, sub, scale, and neg, on Point<i32>, Point<i64>,
+   any other primitive integer, and Point<Fixed<F>>.
+   exact_distance_squared returns the squared distance of
+   integer points as u128, computed with no floating point.
+
+   Fixed<F> is a signed fixed-point number: an i64 holding
+   value * 2^F, so F bits of fraction, with F below 63.  Q16
+   and Q32 name the common choices.  Its operators wrap instead of panicking,
+   and multiply and divide round toward negative infinity, so
+   results are bit-identical on every platform and build.  It
+   implements num_traits Num, so Point<Fixed<F>> gets the
+   geometry operations of other numeric points.
+*/
+use super::geometry::with_coords;
+use super::Point;
+use num_traits::{
+    CheckedAdd, CheckedMul, CheckedNeg, CheckedSub, Num, One, PrimInt, SaturatingAdd,
+    SaturatingMul, SaturatingSub, ToPrimitive, WrappingAdd, WrappingMul, WrappingNeg,
+    WrappingSub, Zero,
+};
+use std::convert::TryFrom;
+use std::fmt::{self, Debug, Display};
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/*-- checked arithmetic --*/
+impl<T> Point<T> where T: Default + Debug + Clone + CheckedAdd + CheckedSub + CheckedMul {
+    pub fn checked_add(&self, other: &Point<T>) -> Option<Point<T>> {
+        Some(with_coords(
+            self,
+            self.x.checked_add(&other.x)?,
+            self.y.checked_add(&other.y)?,
+            self.z.checked_add(&other.z)?,
+        ))
+    }
+    pub fn checked_sub(&self, other: &Point<T>) -> Option<Point<T>> {
+        Some(with_coords(
+            self,
+            self.x.checked_sub(&other.x)?,
+            self.y.checked_sub(&other.y)?,
+            self.z.checked_sub(&other.z)?,
+        ))
+    }
+    pub fn checked_scale(&self, s: T) -> Option<Point<T>> {
+        Some(with_coords(self, self.x.checked_mul(&s)?, self.y.checked_mul(&s)?, self.z.checked_mul(&s)?))
+    }
+    /*-- squared distance in T, None if it doesn't fit --*/
+    pub fn checked_distance_squared(&self, other: &Point<T>) -> Option<T>
+    where T: PartialOrd {
+        let d = |a: &T, b: &T| if a > b { a.checked_sub(b) } else { b.checked_sub(a) };
+        let (dx, dy, dz) = (d(&self.x, &other.x)?, d(&self.y, &other.y)?, d(&self.z, &other.z)?);
+        dx.checked_mul(&dx)?.checked_add(&dy.checked_mul(&dy)?)?.checked_add(&dz.checked_mul(&dz)?)
+    }
+}
+impl<T> Point<T> where T: Default + Debug + Clone + CheckedNeg {
+    pub fn checked_neg(&self) -> Option<Point<T>> {
+        Some(with_coords(self, self.x.checked_neg()?, self.y.checked_neg()?, self.z.checked_neg()?))
+    }
+}
+/*-- saturating arithmetic --*/
+impl<T> Point<T> where T: Default + Debug + Clone + SaturatingAdd + SaturatingSub + SaturatingMul {
+    pub fn saturating_add(&self, other: &Point<T>) -> Point<T> {
+        with_coords(
+            self,
+            self.x.saturating_add(&other.x),
+            self.y.saturating_add(&other.y),
+            self.z.saturating_add(&other.z),
+        )
+    }
+    pub fn saturating_sub(&self, other: &Point<T>) -> Point<T> {
+        with_coords(
+            self,
+            self.x.saturating_sub(&other.x),
+            self.y.saturating_sub(&other.y),
+            self.z.saturating_sub(&other.z),
+        )
+    }
+    pub fn saturating_scale(&self, s: T) -> Point<T> {
+        with_coords(self, self.x.saturating_mul(&s), self.y.saturating_mul(&s), self.z.saturating_mul(&s))
+    }
+}
+/*-- wrapping arithmetic --*/
+impl<T> Point<T> where T: Default + Debug + Clone + WrappingAdd + WrappingSub + WrappingMul {
+    pub fn wrapping_add(&self, other: &Point<T>) -> Point<T> {
+        with_coords(
+            self,
+            self.x.wrapping_add(&other.x),
+            self.y.wrapping_add(&other.y),
+            self.z.wrapping_add(&other.z),
+        )
+    }
+    pub fn wrapping_sub(&self, other: &Point<T>) -> Point<T> {
+        with_coords(
+            self,
+            self.x.wrapping_sub(&other.x),
+            self.y.wrapping_sub(&other.y),
+            self.z.wrapping_sub(&other.z),
+        )
+    }
+    pub fn wrapping_scale(&self, s: T) -> Point<T> {
+        with_coords(self, self.x.wrapping_mul(&s), self.y.wrapping_mul(&s), self.z.wrapping_mul(&s))
+    }
+}
+impl<T> Point<T> where T: Default + Debug + Clone + WrappingNeg {
+    pub fn wrapping_neg(&self) -> Point<T> {
+        with_coords(self, self.x.wrapping_neg(), self.y.wrapping_neg(), self.z.wrapping_neg())
+    }
+}
+/*-----------------------------------------------------------
+  exact squared distance of integer points
+  - always fits for types up to 32 bits
+  - None only when the sum passes u128::MAX, possible for
+    64-bit coordinates differing by more than 2^63 on
+    several axes
+*/
+impl<T> Point<T> where T: Default + Debug + Clone + PrimInt {
+    pub fn exact_distance_squared(&self, other: &Point<T>) -> Option<u128> {
+        let sq = |a: &T, b: &T| -> Option<u128> {
+            let d = a.to_i128()?.checked_sub(b.to_i128()?)?.unsigned_abs();
+            d.checked_mul(d)
+        };
+        sq(&self.x, &other.x)?.checked_add(sq(&self.y, &other.y)?)?.checked_add(sq(&self.z, &other.z)?)
+    }
+}
+
+/*-- define Fixed<F> type, raw / 2^F --*/
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed<const F: u32>(i64);
+pub type Q16 = Fixed<16>;
+pub type Q32 = Fixed<32>;
+
+impl<const F: u32> Fixed<F> {
+    /*-- fails to compile when a constructor is used with F >= 63 --*/
+    const VALID: () = assert!(F < 63, "Fixed<F> needs F < 63");
+    pub const ONE: Fixed<F> = {
+        let () = Fixed::<F>::VALID;
+        Fixed(1 << F)
+    };
+    pub const MAX: Fixed<F> = Fixed(i64::MAX);
+    pub const MIN: Fixed<F> = Fixed(i64::MIN);
+    pub const fn from_raw(raw: i64) -> Fixed<F> {
+        let () = Fixed::<F>::VALID;
+        Fixed(raw)
+    }
+    pub const fn raw(self) -> i64 {
+        self.0
+    }
+    /*-- wraps if n doesn't fit in 63 - F integer bits --*/
+    pub const fn from_int(n: i64) -> Fixed<F> {
+        let () = Fixed::<F>::VALID;
+        Fixed(n.wrapping_shl(F))
+    }
+    /*-- nearest representable value, None for NaN or out of range --*/
+    pub fn from_f64(v: f64) -> Option<Fixed<F>> {
+        let () = Fixed::<F>::VALID;
+        let r = (v * (1u64 << F) as f64).round();
+        if r.is_finite() && r >= i64::MIN as f64 && r < i64::MAX as f64 {
+            Some(Fixed(r as i64))
+        } else {
+            None
+        }
+    }
+    pub fn to_f64(self) -> f64 {
+        let () = Fixed::<F>::VALID;
+        self.0 as f64 / (1u64 << F) as f64
+    }
+    /*-- integer part, rounded toward negative infinity --*/
+    pub const fn floor(self) -> i64 {
+        self.0 >> F
+    }
+    fn mul_raw(a: i64, b: i64) -> i128 {
+        (a as i128 * b as i128) >> F
+    }
+    fn div_raw(a: i64, b: i64) -> Option<i128> {
+        if b == 0 {
+            return None;
+        }
+        let (n, d) = ((a as i128) << F, b as i128);
+        let (q, r) = (n / d, n % d);
+        Some(if r != 0 && (r < 0) != (d < 0) { q - 1 } else { q })
+    }
+    /*-- None on division by zero or overflow --*/
+    pub fn checked_div(self, rhs: Fixed<F>) -> Option<Fixed<F>> {
+        Fixed::<F>::div_raw(self.0, rhs.0).and_then(|q| i64::try_from(q).ok()).map(Fixed)
+    }
+}
+impl<const F: u32> Debug for Fixed<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", Fixed::to_f64(*self))
+    }
+}
+impl<const F: u32> Display for Fixed<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Fixed::to_f64(*self))
+    }
+}
+/*-- operators wrap, so results never depend on build mode --*/
+impl<const F: u32> Add for Fixed<F> {
+    type Output = Fixed<F>;
+    fn add(self, rhs: Fixed<F>) -> Fixed<F> {
+        Fixed(self.0.wrapping_add(rhs.0))
+    }
+}
+impl<const F: u32> Sub for Fixed<F> {
+    type Output = Fixed<F>;
+    fn sub(self, rhs: Fixed<F>) -> Fixed<F> {
+        Fixed(self.0.wrapping_sub(rhs.0))
+    }
+}
+impl<const F: u32> Mul for Fixed<F> {
+    type Output = Fixed<F>;
+    fn mul(self, rhs: Fixed<F>) -> Fixed<F> {
+        Fixed(Fixed::<F>::mul_raw(self.0, rhs.0) as i64)
+    }
+}
+/*-- panics on division by zero, like integer division --*/
+impl<const F: u32> Div for Fixed<F> {
+    type Output = Fixed<F>;
+    fn div(self, rhs: Fixed<F>) -> Fixed<F> {
+        Fixed(Fixed::<F>::div_raw(self.0, rhs.0).expect("fixed-point division by zero") as i64)
+    }
+}
+/*-- remainder of the raw values, panics when rhs is zero, like Div --*/
+impl<const F: u32> Rem for Fixed<F> {
+    type Output = Fixed<F>;
+    fn rem(self, rhs: Fixed<F>) -> Fixed<F> {
+        Fixed(self.0.wrapping_rem(rhs.0))
+    }
+}
+impl<const F: u32> Neg for Fixed<F> {
+    type Output = Fixed<F>;
+    fn neg(self) -> Fixed<F> {
+        Fixed(self.0.wrapping_neg())
+    }
+}
+impl<const F: u32> Zero for Fixed<F> {
+    fn zero() -> Fixed<F> {
+        Fixed(0)
+    }
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+impl<const F: u32> One for Fixed<F> {
+    fn one() -> Fixed<F> {
+        Fixed::ONE
+    }
+}
+impl<const F: u32> Num for Fixed<F> {
+    type FromStrRadixErr = ();
+    /*-- decimal text only, e.g., "-12.375" --*/
+    fn from_str_radix(s: &str, radix: u32) -> Result<Fixed<F>, ()> {
+        if radix != 10 {
+            return Err(());
+        }
+        s.trim().parse::<f64>().ok().and_then(Fixed::from_f64).ok_or(())
+    }
+}
+impl<const F: u32> std::str::FromStr for Fixed<F> {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Fixed<F>, ()> {
+        Fixed::from_str_radix(s, 10)
+    }
+}
+impl<const F: u32> ToPrimitive for Fixed<F> {
+    fn to_i64(&self) -> Option<i64> {
+        Some(self.floor())
+    }
+    fn to_u64(&self) -> Option<u64> {
+        u64::try_from(self.floor()).ok()
+    }
+    fn to_f64(&self) -> Option<f64> {
+        Some(Fixed::to_f64(*self))
+    }
+}
+impl<const F: u32> CheckedAdd for Fixed<F> {
+    fn checked_add(&self, v: &Fixed<F>) -> Option<Fixed<F>> {
+        self.0.checked_add(v.0).map(Fixed)
+    }
+}
+impl<const F: u32> CheckedSub for Fixed<F> {
+    fn checked_sub(&self, v: &Fixed<F>) -> Option<Fixed<F>> {
+        self.0.checked_sub(v.0).map(Fixed)
+    }
+}
+impl<const F: u32> CheckedMul for Fixed<F> {
+    fn checked_mul(&self, v: &Fixed<F>) -> Option<Fixed<F>> {
+        i64::try_from(Fixed::<F>::mul_raw(self.0, v.0)).ok().map(Fixed)
+    }
+}
+impl<const F: u32> CheckedNeg for Fixed<F> {
+    fn checked_neg(&self) -> Option<Fixed<F>> {
+        self.0.checked_neg().map(Fixed)
+    }
+}
+impl<const F: u32> SaturatingAdd for Fixed<F> {
+    fn saturating_add(&self, v: &Fixed<F>) -> Fixed<F> {
+        Fixed(self.0.saturating_add(v.0))
+    }
+}
+impl<const F: u32> SaturatingSub for Fixed<F> {
+    fn saturating_sub(&self, v: &Fixed<F>) -> Fixed<F> {
+        Fixed(self.0.saturating_sub(v.0))
+    }
+}
+impl<const F: u32> SaturatingMul for Fixed<F> {
+    fn saturating_mul(&self, v: &Fixed<F>) -> Fixed<F> {
+        let p = Fixed::<F>::mul_raw(self.0, v.0);
+        Fixed(p.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+impl<const F: u32> WrappingAdd for Fixed<F> {
+    fn wrapping_add(&self, v: &Fixed<F>) -> Fixed<F> {
+        *self + *v
+    }
+}
+impl<const F: u32> WrappingSub for Fixed<F> {
+    fn wrapping_sub(&self, v: &Fixed<F>) -> Fixed<F> {
+        *self - *v
+    }
+}
+impl<const F: u32> WrappingMul for Fixed<F> {
+    fn wrapping_mul(&self, v: &Fixed<F>) -> Fixed<F> {
+        *self * *v
+    }
+}
+impl<const F: u32> WrappingNeg for Fixed<F> {
+    fn wrapping_neg(&self) -> Fixed<F> {
+        -*self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpaceTime;
+    fn ipt<T: Default + Debug + Clone>(c: [T; 3]) -> Point<T> {
+        let mut p = Point::new();
+        p.set_coordinates(&c);
+        p
+    }
+    #[test]
+    fn integer_overflow_variants() {
+        let a = ipt([i32::MAX, 1, -1]);
+        let b = ipt([1, 2, 3]);
+        assert!(a.checked_add(&b).is_none());
+        assert_eq!(b.checked_add(&b).unwrap().get_coordinates(), [2, 4, 6]);
+        assert_eq!(a.saturating_add(&b).get_coordinates(), [i32::MAX, 3, 2]);
+        assert_eq!(a.wrapping_add(&b).get_coordinates(), [i32::MIN, 3, 2]);
+        assert_eq!(ipt([i32::MIN, 0, 0]).checked_neg(), None);
+        assert_eq!(ipt([i32::MIN, 0, 0]).wrapping_neg().get_coordinates(), [i32::MIN, 0, 0]);
+        assert_eq!(ipt([i64::MIN, 0, 0]).saturating_scale(2).get_coordinates()[0], i64::MIN);
+        assert!(ipt([0u8, 0, 0]).checked_sub(&ipt([1, 0, 0])).is_none());
+        assert_eq!(ipt([1u8, 2, 3]).wrapping_scale(100).get_coordinates(), [100, 200, 44]);
+    }
+    #[test]
+    fn exact_squared_distances() {
+        let a = ipt([i32::MIN, i32::MIN, i32::MIN]);
+        let b = ipt([i32::MAX, i32::MAX, i32::MAX]);
+        let d = (u32::MAX as u128) * (u32::MAX as u128) * 3;
+        assert_eq!(a.exact_distance_squared(&b), Some(d));
+        assert_eq!(a.checked_distance_squared(&b), None);
+        assert_eq!(ipt([3i64, 4, 0]).checked_distance_squared(&ipt([0, 0, 0])), Some(25));
+        let (lo, hi) = (ipt([i64::MIN; 3]), ipt([i64::MAX; 3]));
+        assert_eq!(lo.exact_distance_squared(&hi), None);
+        assert_eq!(ipt([i64::MIN, 0, 0]).exact_distance_squared(&ipt([i64::MAX, 0, 0])), Some((u64::MAX as u128).pow(2)));
+        assert_eq!(ipt([1u64 << 62, 0, 0]).exact_distance_squared(&ipt([0, 0, 0])), Some(1u128 << 124));
+    }
+    #[test]
+    fn fixed_point() {
+        let a = Q16::from_f64(1.5).unwrap();
+        let b = Q16::from_int(-2);
+        assert_eq!((a * b).to_f64(), -3.0);
+        assert_eq!((b / a).raw(), -87382);
+        assert_eq!((a + b).to_string(), "-0.5");
+        assert_eq!((-a).floor(), -2);
+        assert!(a.checked_div(Q16::zero()).is_none());
+        assert_eq!(a.checked_div(-Q16::ONE).unwrap(), -a);
+        assert_eq!(Q16::from_raw(1).checked_div(-Q16::from_int(2)).unwrap().raw(), -1);
+        assert_eq!("2.25".parse::<Q16>().unwrap(), Q16::from_f64(2.25).unwrap());
+        assert!(Q32::MAX.checked_add(&Q32::ONE).is_none());
+        assert_eq!(Q32::MAX.saturating_mul(&Q32::from_int(2)), Q32::MAX);
+        let p = ipt([Q16::from_int(3), Q16::from_int(4), Q16::zero()]);
+        let q = ipt([Q16::zero(); 3]);
+        assert_eq!(p.distance_squared(&q), Q16::from_int(25));
+        assert_eq!(p.checked_add(&p).unwrap().get_coordinates()[1], Q16::from_int(8));
+        assert_eq!(p.dot(&p).to_f64(), 25.0);
+    }
+}
@@ -0,0 +1,354 @@
+/////////////////////////////////////////////////////////////
+// point::kalman.rs - Kalman filter for noisy positions    //
+/////////////////////////////////////////////////////////////
+/*
+   KalmanFilter smooths timestamped position measurements,
+   taken from any SpaceTime<f64>, and estimates velocity and,
+   optionally, acceleration.
+   - Motion::ConstantVelocity:      state is position, velocity,
+                                    driven by white-noise
+                                    acceleration
+   - Motion::ConstantAcceleration:  state adds acceleration,
+                                    driven by white-noise jerk
+   Measurement noise is independent in x, y, and z, so each
+   axis is filtered separately and the full covariance is
+   block diagonal.  Estimate::covariance assembles it in state
+   order: x, y, z, vx, vy, vz, then ax, ay, az.
+
+   The first measurement initializes the state, with large
+   variance for velocity and acceleration.  Measurements must
+   arrive in time order.
+*/
+use super::trajectory::seconds;
+use super::{Point, SpaceTime};
+use chrono::{DateTime, Utc};
+use std::fmt::{self, Display};
+
+/*-- variance given to unobserved states at start --*/
+const INITIAL_VARIANCE: f64 = 1.0e6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    ConstantVelocity,
+    ConstantAcceleration,
+}
+impl Motion {
+    /*-- number of states per axis --*/
+    fn order(self) -> usize {
+        match self {
+            Motion::ConstantVelocity => 2,
+            Motion::ConstantAcceleration => 3,
+        }
+    }
+}
+
+/*-- reasons a measurement is rejected --*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum KalmanError {
+    /*-- measurement time is before the last accepted time --*/
+    OutOfOrder { last: DateTime<Utc>, got: DateTime<Utc> },
+    /*-- a coordinate is NaN or infinite --*/
+    NotFinite([f64; 3]),
+}
+impl Display for KalmanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KalmanError::OutOfOrder { last, got } => {
+                write!(f, "measurement at {} is before last update at {}", got.to_rfc3339(), last.to_rfc3339())
+            }
+            KalmanError::NotFinite(c) => write!(f, "measurement {:?} is not finite", c),
+        }
+    }
+}
+impl std::error::Error for KalmanError {}
+
+/*-- one axis: state and covariance, only the first order entries are used --*/
+type Axis = ([f64; 3], [[f64; 3]; 3]);
+
+/*-----------------------------------------------------------
+  filter output at one time
+  - acceleration is None for the constant-velocity model
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate {
+    pub time: DateTime<Utc>,
+    pub position: [f64; 3],
+    pub velocity: [f64; 3],
+    pub acceleration: Option<[f64; 3]>,
+    order: usize,
+    axes: [Axis; 3],
+}
+impl Estimate {
+    pub fn position_variance(&self) -> [f64; 3] {
+        [self.axes[0].1[0][0], self.axes[1].1[0][0], self.axes[2].1[0][0]]
+    }
+    pub fn velocity_variance(&self) -> [f64; 3] {
+        [self.axes[0].1[1][1], self.axes[1].1[1][1], self.axes[2].1[1][1]]
+    }
+    /*-- full state covariance, 6x6 or 9x9, see module comment for order --*/
+    pub fn covariance(&self) -> Vec<Vec<f64>> {
+        let n = 3 * self.order;
+        let mut c = vec![vec![0.0; n]; n];
+        for (a, (_, p)) in self.axes.iter().enumerate() {
+            for i in 0..self.order {
+                for j in 0..self.order {
+                    c[3 * i + a][3 * j + a] = p[i][j];
+                }
+            }
+        }
+        c
+    }
+    /*-- estimated position as a point at the estimate time --*/
+    pub fn to_point(&self) -> Point<f64> {
+        let mut pt = Point::new();
+        pt.set_coordinates(&self.position);
+        pt.set_time(self.time);
+        pt
+    }
+}
+
+/*-- define KalmanFilter type --*/
+#[derive(Debug, Clone)]
+pub struct KalmanFilter {
+    motion: Motion,
+    process_noise: f64,
+    measurement_noise: [f64; 3],
+    state: Option<(DateTime<Utc>, [Axis; 3])>,
+}
+impl KalmanFilter {
+    /*-----------------------------------------------------------
+      process_noise is the spectral density of the driving
+      noise, acceleration for ConstantVelocity, jerk for
+      ConstantAcceleration.  measurement_noise is the variance
+      of each measured coordinate.
+    */
+    pub fn new(motion: Motion, process_noise: f64, measurement_noise: f64) -> KalmanFilter {
+        KalmanFilter {
+            motion,
+            process_noise,
+            measurement_noise: [measurement_noise; 3],
+            state: None,
+        }
+    }
+    pub fn constant_velocity(process_noise: f64, measurement_noise: f64) -> KalmanFilter {
+        KalmanFilter::new(Motion::ConstantVelocity, process_noise, measurement_noise)
+    }
+    pub fn constant_acceleration(process_noise: f64, measurement_noise: f64) -> KalmanFilter {
+        KalmanFilter::new(Motion::ConstantAcceleration, process_noise, measurement_noise)
+    }
+    /*-- separate measurement variance for x, y, z --*/
+    pub fn with_measurement_noise(mut self, variance: [f64; 3]) -> KalmanFilter {
+        self.measurement_noise = variance;
+        self
+    }
+    pub fn motion(&self) -> Motion {
+        self.motion
+    }
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+    /*-- latest estimate, None before the first measurement --*/
+    pub fn estimate(&self) -> Option<Estimate> {
+        self.state.as_ref().map(|(t, axes)| self.make_estimate(*t, *axes))
+    }
+    /*-- estimate extrapolated to time t, filter state unchanged --*/
+    pub fn predict(&self, t: DateTime<Utc>) -> Option<Estimate> {
+        let (last, axes) = self.state.as_ref()?;
+        let dt = seconds(t - *last);
+        let mut axes = *axes;
+        for axis in axes.iter_mut() {
+            self.predict_axis(axis, dt);
+        }
+        Some(self.make_estimate(t, axes))
+    }
+    /*-- fold one measurement into the state and return the new estimate --*/
+    pub fn update<S>(&mut self, m: &S) -> Result<Estimate, KalmanError>
+    where S: SpaceTime<f64> + ?Sized {
+        let (t, z) = (m.get_time(), m.get_coordinates());
+        if !z.iter().all(|v| v.is_finite()) {
+            return Err(KalmanError::NotFinite(z));
+        }
+        let axes = match self.state.take() {
+            None => self.initial_axes(&z),
+            Some((last, mut axes)) => {
+                if t < last {
+                    self.state = Some((last, axes));
+                    return Err(KalmanError::OutOfOrder { last, got: t });
+                }
+                let dt = seconds(t - last);
+                for (a, axis) in axes.iter_mut().enumerate() {
+                    self.predict_axis(axis, dt);
+                    self.correct_axis(axis, z[a], self.measurement_noise[a]);
+                }
+                axes
+            }
+        };
+        self.state = Some((t, axes));
+        Ok(self.make_estimate(t, axes))
+    }
+    /*-- filter a whole series, stops at the first rejected measurement --*/
+    pub fn filter_all<'a, S, I>(&mut self, measurements: I) -> Result<Vec<Estimate>, KalmanError>
+    where S: SpaceTime<f64> + 'a, I: IntoIterator<Item = &'a S> {
+        measurements.into_iter().map(|m| self.update(m)).collect()
+    }
+
+    fn initial_axes(&self, z: &[f64; 3]) -> [Axis; 3] {
+        let axis = |a: usize| {
+            let mut p = [[0.0; 3]; 3];
+            p[0][0] = self.measurement_noise[a];
+            for (i, row) in p.iter_mut().enumerate().skip(1) {
+                row[i] = INITIAL_VARIANCE;
+            }
+            ([z[a], 0.0, 0.0], p)
+        };
+        [axis(0), axis(1), axis(2)]
+    }
+    /*-- x = F x,  P = F P F' + Q --*/
+    fn predict_axis(&self, axis: &mut Axis, dt: f64) {
+        let k = self.motion.order();
+        let (x, p) = axis;
+        let f = transition(k, dt);
+        let q = process(k, dt, self.process_noise);
+        let mut nx = [0.0; 3];
+        let mut fp = [[0.0; 3]; 3];
+        for i in 0..k {
+            for j in 0..k {
+                nx[i] += f[i][j] * x[j];
+                for (l, prow) in p.iter().enumerate().take(k) {
+                    fp[i][j] += f[i][l] * prow[j];
+                }
+            }
+        }
+        let mut np = [[0.0; 3]; 3];
+        for i in 0..k {
+            for j in 0..k {
+                np[i][j] = (0..k).fold(q[i][j], |acc, l| acc + fp[i][l] * f[j][l]);
+            }
+        }
+        *x = nx;
+        *p = np;
+    }
+    /*-- scalar measurement of position, H = [1, 0, 0] --*/
+    fn correct_axis(&self, axis: &mut Axis, z: f64, r: f64) {
+        let k = self.motion.order();
+        let (x, p) = axis;
+        let s = p[0][0] + r;
+        if s <= 0.0 {
+            return;
+        }
+        let gain: Vec<f64> = (0..k).map(|i| p[i][0] / s).collect();
+        let innovation = z - x[0];
+        let row0 = p[0];
+        for i in 0..k {
+            x[i] += gain[i] * innovation;
+            for j in 0..k {
+                p[i][j] -= gain[i] * row0[j];
+            }
+        }
+        /*-- keep P symmetric against rounding --*/
+        for (i, j) in (0..k).flat_map(|i| ((i + 1)..k).map(move |j| (i, j))) {
+            let m = 0.5 * (p[i][j] + p[j][i]);
+            p[i][j] = m;
+            p[j][i] = m;
+        }
+    }
+    fn make_estimate(&self, time: DateTime<Utc>, axes: [Axis; 3]) -> Estimate {
+        let k = self.motion.order();
+        let comp = |i: usize| [axes[0].0[i], axes[1].0[i], axes[2].0[i]];
+        Estimate {
+            time,
+            position: comp(0),
+            velocity: comp(1),
+            acceleration: if k == 3 { Some(comp(2)) } else { None },
+            order: k,
+            axes,
+        }
+    }
+}
+
+fn transition(k: usize, dt: f64) -> [[f64; 3]; 3] {
+    let mut f = [[1.0, dt, 0.5 * dt * dt], [0.0, 1.0, dt], [0.0, 0.0, 1.0]];
+    if k == 2 {
+        f[0][2] = 0.0;
+    }
+    f
+}
+/*-- discrete process noise for white-noise acceleration or jerk --*/
+fn process(k: usize, dt: f64, q: f64) -> [[f64; 3]; 3] {
+    let d = |n: i32| dt.powi(n);
+    if k == 2 {
+        [[d(3) / 3.0 * q, d(2) / 2.0 * q, 0.0], [d(2) / 2.0 * q, dt * q, 0.0], [0.0; 3]]
+    } else {
+        [
+            [d(5) / 20.0 * q, d(4) / 8.0 * q, d(3) / 6.0 * q],
+            [d(4) / 8.0 * q, d(3) / 3.0 * q, d(2) / 2.0 * q],
+            [d(3) / 6.0 * q, d(2) / 2.0 * q, dt * q],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    /*-- repeatable noise in [-1, 1] --*/
+    fn noise(i: u64) -> f64 {
+        let h = (i.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 11) as f64 / (1u64 << 53) as f64;
+        2.0 * h - 1.0
+    }
+    fn track(n: i64, f: impl Fn(f64) -> [f64; 3]) -> Vec<Point<f64>> {
+        let t0 = Utc::now();
+        (0..n).map(|i| {
+            let mut pt = Point::new();
+            let c = f(i as f64);
+            let k = 3 * i as u64;
+            pt.set_coordinates(&[c[0] + noise(k), c[1] + noise(k + 1), c[2] + noise(k + 2)]);
+            pt.set_time(t0 + Duration::seconds(i));
+            pt
+        }).collect()
+    }
+    #[test]
+    fn constant_velocity_track() {
+        let pts = track(60, |t| [10.0 * t, -5.0 * t, 100.0]);
+        let mut kf = KalmanFilter::constant_velocity(0.01, 1.0 / 3.0);
+        let est = kf.filter_all(&pts).unwrap();
+        let last = est.last().unwrap();
+        assert!((last.velocity[0] - 10.0).abs() < 0.2);
+        assert!((last.velocity[1] + 5.0).abs() < 0.2);
+        assert!(last.velocity[2].abs() < 0.2);
+        assert!(last.acceleration.is_none());
+        assert!(last.position_variance()[0] < est[0].position_variance()[0]);
+        let cov = last.covariance();
+        assert_eq!(cov.len(), 6);
+        assert_eq!(cov[0][3], cov[3][0]);
+        assert_eq!(cov[0][1], 0.0);
+        let ahead = kf.predict(last.time + Duration::seconds(2)).unwrap();
+        assert!((ahead.position[0] - last.position[0] - 2.0 * last.velocity[0]).abs() < 1e-9);
+        assert!(ahead.position_variance()[0] > last.position_variance()[0]);
+    }
+    #[test]
+    fn constant_acceleration_track() {
+        let pts = track(80, |t| [0.5 * 2.0 * t * t, 3.0 * t, 0.0]);
+        let mut kf = KalmanFilter::constant_acceleration(0.001, 1.0 / 3.0);
+        let last = kf.filter_all(&pts).unwrap().pop().unwrap();
+        let acc = last.acceleration.unwrap();
+        assert!((acc[0] - 2.0).abs() < 0.1);
+        assert!((last.velocity[0] - 2.0 * 79.0).abs() < 1.0);
+        assert_eq!(last.covariance().len(), 9);
+        assert_eq!(last.to_point().get_time(), pts[79].get_time());
+    }
+    #[test]
+    fn rejects_bad_measurements() {
+        let pts = track(3, |t| [t, t, t]);
+        let mut kf = KalmanFilter::constant_velocity(1.0, 1.0).with_measurement_noise([1.0, 2.0, 4.0]);
+        assert!(kf.estimate().is_none());
+        kf.update(&pts[2]).unwrap();
+        let e = kf.update(&pts[0]).unwrap_err();
+        assert!(matches!(e, KalmanError::OutOfOrder { .. }));
+        assert_eq!(kf.estimate().unwrap().time, pts[2].get_time());
+        let mut bad = pts[2].clone();
+        bad.set_coordinates(&[f64::NAN, 0.0, 0.0]);
+        assert!(matches!(kf.update(&bad), Err(KalmanError::NotFinite(_))));
+        assert_eq!(kf.estimate().unwrap().position_variance(), [1.0, 2.0, 4.0]);
+    }
+}
@@ -22,51 +22,155 @@
    e.g., ids, other information, ... but a function that accepts 
    a SpaceTime trait object can extract that information from any 
    of them.
+
+   With the "serde" feature enabled, Point<T> implements serde's
+   Serialize and Deserialize, so points, including their time and
+   name, can be saved and loaded with any serde format, e.g., JSON,
+   CBOR, or bincode.
+
+   The geometry module adds vector arithmetic, products, and
+   distances for Point<T>.  Trajectory<T> holds a time-ordered
+   series of points and interpolates positions between them.
+   KdTree indexes SpaceTime objects for fast spatial queries.
+   GeoPoint holds WGS-84 latitude, longitude, and altitude and
+   converts to Earth-centered (ECEF) and local East-North-Up
+   frames.
+
+   Times are held in UTC, so values recorded in different time
+   zones compare and merge correctly, and text output does not
+   depend on the machine's zone.  SpaceTime provides helpers
+   for converting to Local or any other chrono TimeZone, and
+   format_time writes RFC-3339, ISO-8601 basic, or Unix seconds
+   with selectable sub-second precision.
+
+   Point<T> implements FromStr, reading both its Display form
+   and CSV lines, see the parse module.  The point_io module
+   streams batches of points to and from CSV and NDJSON, and the
+   recorder module keeps a crash-tolerant binary flight log.
+
+   PointN<T, N> and SpaceTimeN<T, N> generalize Point and
+   SpaceTime to any fixed number of coordinates.  The transform
+   module rotates and translates points with matrices,
+   quaternions, and rigid-body transforms.
+
+   KalmanFilter smooths noisy timestamped positions and
+   estimates velocity and acceleration with their covariance.
+   Geofence watches named SpaceTime streams and reports when
+   they enter, exit, or dwell in polygon, cylinder, or sphere
+   zones, e.g., for port area management.  The cpa module finds
+   the closest approach of two trajectories and detects losses
+   of separation between tracks.  The simplify module thins
+   dense tracks and resamples them to a fixed time step.
+   The stats module computes bounding boxes, centroids,
+   covariance, principal axes, and time spans of collections.
+   The export module writes points and tracks as GeoJSON or
+   KML for GIS viewers.  Point<Length<T, U>> holds coordinates
+   tagged with a unit, so feet and meters can't be mixed, see
+   the units module.  The integer module adds checked,
+   saturating, and wrapping arithmetic for integer points, and
+   a deterministic fixed-point coordinate type, Fixed<F>.
+   Simulator generates seeded, reproducible point streams for
+   tests and demos.  The window module summarizes streams in
+   tumbling or sliding time windows, optionally per name.
+   Tracker assigns anonymous measurements to named tracks.
 */
-use chrono::offset::Local;
-use chrono::DateTime;
-use chrono::{Datelike, Timelike};
+use chrono::offset::{Local, Utc};
+use chrono::{DateTime, TimeZone};
 use std::fmt::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+mod geometry;
+pub mod trajectory;
+pub use trajectory::{Interpolation, Trajectory};
+pub mod spatial;
+pub use spatial::{KdTree, TimeWindow};
+pub mod geodetic;
+pub use geodetic::GeoPoint;
+pub mod time_format;
+pub use time_format::{parse_time, Precision, TimeFormat};
+pub mod parse;
+pub use parse::{Field, ParsePointError};
+pub mod point_io;
+pub use point_io::{CsvReader, CsvWriter, NdjsonReader, NdjsonWriter, PointFormat, ReadError};
+pub mod recorder;
+pub use recorder::{FlightReader, FlightRecorder, Recordable, RecorderError};
+pub mod point_n;
+pub use point_n::{PointN, SpaceTimeN};
+pub mod transform;
+pub use transform::{Mat3, Mat4, Quaternion, RigidTransform, Transform};
+pub mod kalman;
+pub use kalman::{Estimate, KalmanError, KalmanFilter, Motion};
+pub mod geofence;
+pub use geofence::{FenceEvent, FenceEventKind, Geofence, Named, Shape, Zone};
+pub mod cpa;
+pub use cpa::{closest_approach, Approach, Conflict, ConflictDetector};
+pub mod simplify;
+pub use simplify::{douglas_peucker, resample, visvalingam};
+pub mod stats;
+pub use stats::{Aabb, PrincipalAxes, Summary};
+pub mod export;
+pub use export::{GeoExport, GeoJson, Kml};
+pub mod units;
+pub use units::{Feet, Length, Meters, NauticalMiles, Unit, UnitPoint};
+pub mod integer;
+pub use integer::{Fixed, Q16, Q32};
+pub mod simulator;
+pub use simulator::{Route, Simulator, SplitMix64};
+pub mod window;
+pub use window::{aggregate_windows, WindowAggregate, WindowSpec, Windower};
+pub mod tracker;
+pub use tracker::{Association, ScanReport, Track, TrackState, Tracker, TrackerConfig};
 
 /*-----------------------------------------------------------
   declare SpaceTime<T> trait
   - note that this trait is generic  
+  - times are UTC, provided methods convert to other zones
 */
 pub trait SpaceTime<T> {
     fn get_coordinates(&self) -> [T; 3];
     fn set_coordinates(&mut self, coor: &[T; 3]);
-    fn set_time(&mut self, st: DateTime<Local>);
-    fn get_time(&self) -> DateTime<Local>;
-    fn get_time_string(&self) -> String;
+    fn set_time(&mut self, st: DateTime<Utc>);
+    fn get_time(&self) -> DateTime<Utc>;
+    /*-- RFC-3339 in UTC, e.g., 2020-06-24T13:05:09.250Z --*/
+    fn get_time_string(&self) -> String {
+        self.format_time(TimeFormat::default())
+    }
+    fn format_time(&self, fmt: TimeFormat) -> String {
+        fmt.format(&self.get_time())
+    }
+    fn get_local_time(&self) -> DateTime<Local> {
+        self.get_time().with_timezone(&Local)
+    }
+    fn set_local_time(&mut self, st: DateTime<Local>) {
+        self.set_time(st.with_timezone(&Utc));
+    }
+    /*-- time in any chrono TimeZone, e.g., FixedOffset --*/
+    fn get_time_in<Tz: TimeZone>(&self, tz: &Tz) -> DateTime<Tz>
+    where Self: Sized {
+        self.get_time().with_timezone(tz)
+    }
+    fn set_time_from<Tz: TimeZone>(&mut self, st: &DateTime<Tz>)
+    where Self: Sized {
+        self.set_time(st.with_timezone(&Utc));
+    }
 }
 /*-- define Point<T> type --*/
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Point<T> 
 where T:Default + Debug {
-    x:T, y:T, z:T, t:DateTime<Local>, n:String,
+    x:T, y:T, z:T, t:DateTime<Utc>, n:String,
 }
 /*-- implement Time trait --*/
 impl<T> SpaceTime<T> for Point<T>
 where T:Default + Debug + Clone {
-    fn set_time(&mut self, st: DateTime<Local>) {
+    fn set_time(&mut self, st: DateTime<Utc>) {
         self.t = st;
     }
-    fn get_time(&self) -> DateTime<Local> {
+    fn get_time(&self) -> DateTime<Utc> {
         self.t
     }
-    fn get_time_string(&self) -> String {
-        let year = self.t.year().to_string();
-        let mon = self.t.month().to_string();
-        let day = self.t.day().to_string();
-        let hour = self.t.hour().to_string();
-        let min = self.t.minute().to_string();
-        let sec = self.t.second().to_string();
-        let dt = format!(
-            "{}::{:0>2}::{:0>2} {:0>2}::{:0>2}::{:0>2}", 
-            year, mon, day, hour, min, sec
-        );
-        dt
-    }
     /*-- set coordinates from array slice --*/
     fn set_coordinates(&mut self, coor: &[T; 3]) {
         self.x = coor[0].clone();
@@ -97,13 +201,10 @@ impl<T> Point<T> where T:Default + Debug + Clone {
             x:std::default::Default::default(), 
             y:std::default::Default::default(), 
             z:std::default::Default::default(),
-            t:Local::now(),
+            t:Utc::now(),
             n:String::default()
         }
     }
-    pub fn default() -> Point<T> {
-        Point::new()
-    }
     pub fn set_name(&mut self, name: &str) {
         self.n = name.to_string();
     }
@@ -111,6 +212,12 @@ impl<T> Point<T> where T:Default + Debug + Clone {
         &self.n
     }
 }
+/*-- Default is the same as new() --*/
+impl<T> Default for Point<T> where T:Default + Debug + Clone {
+    fn default() -> Point<T> {
+        Point::new()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -130,11 +237,63 @@ mod tests {
     fn get_time() {
         use chrono::{Duration};
         let two_sec = Duration::seconds(2);
-        let ts_start = Local::now() - two_sec;
+        let ts_start = Utc::now() - two_sec;
         let pt = Point::<i32>::new();
         let ts_now = pt.get_time();
-        let ts_stop = Local::now() + two_sec;
+        let ts_stop = Utc::now() + two_sec;
         assert!(ts_start < ts_now);
         assert!(ts_now < ts_stop);
     }
+    #[test]
+    fn time_zones() {
+        use chrono::FixedOffset;
+        let mut pt = Point::<i32>::new();
+        let east = FixedOffset::east_opt(5 * 3600).unwrap();
+        let t = east.timestamp_opt(1_593_003_909, 0).unwrap();
+        pt.set_time_from(&t);
+        assert_eq!(pt.get_time_in(&east), t);
+        assert_eq!(pt.get_time_string(), "2020-06-24T13:05:09Z");
+        assert_eq!(pt.get_local_time(), t);
+        let mut other = Point::<i32>::new();
+        other.set_local_time(pt.get_local_time());
+        assert_eq!(other.get_time(), pt.get_time());
+    }
+    #[cfg(feature = "serde")]
+    fn test_point() -> Point<f64> {
+        let mut pt = Point::<f64>::new();
+        pt.set_coordinates(&[1.5, -2.0, 3.25]);
+        pt.set_name("recorded pt");
+        pt
+    }
+    #[cfg(feature = "serde")]
+    fn assert_same(pt: &Point<f64>, rt: &Point<f64>) {
+        assert_eq!(pt.get_coordinates(), rt.get_coordinates());
+        assert_eq!(pt.get_time(), rt.get_time());
+        assert_eq!(pt.get_name(), rt.get_name());
+    }
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_json_round_trip() {
+        let pt = test_point();
+        let s = serde_json::to_string(&pt).unwrap();
+        let rt: Point<f64> = serde_json::from_str(&s).unwrap();
+        assert_same(&pt, &rt);
+    }
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_cbor_round_trip() {
+        let pt = test_point();
+        let mut buf = Vec::<u8>::new();
+        ciborium::ser::into_writer(&pt, &mut buf).unwrap();
+        let rt: Point<f64> = ciborium::de::from_reader(&buf[..]).unwrap();
+        assert_same(&pt, &rt);
+    }
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_bincode_round_trip() {
+        let pt = test_point();
+        let buf = bincode::serialize(&pt).unwrap();
+        let rt: Point<f64> = bincode::deserialize(&buf).unwrap();
+        assert_same(&pt, &rt);
+    }
 }
@@ -0,0 +1,240 @@
+/////////////////////////////////////////////////////////////
+// point::parse.rs - Read Point<T> back from text          //
+/////////////////////////////////////////////////////////////
+/*
+   Point<T> implements FromStr for two text forms:
+   - Display form:  { 1.5, -2.0, 3.25, 2020-06-24T13:05:09Z, "pt" }
+   - CSV line:      1.5,-2.0,3.25,2020-06-24T13:05:09Z,pt
+   Text starting with '{' is read as Display form, anything
+   else as CSV.  CSV names may be quoted, with "" standing
+   for a quote inside the name.  The name may be omitted in
+   CSV.  Times may be in any layout TimeFormat writes.
+
+   Failures return ParsePointError, which names the Field
+   that could not be read.
+*/
+use super::time_format::parse_time;
+use super::Point;
+use std::fmt::{self, Debug, Display};
+use std::str::FromStr;
+
+/*-- fields of the text forms, in order --*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    X,
+    Y,
+    Z,
+    Time,
+    Name,
+}
+impl Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Field::X => "x",
+            Field::Y => "y",
+            Field::Z => "z",
+            Field::Time => "time",
+            Field::Name => "name",
+        };
+        f.write_str(s)
+    }
+}
+
+/*-- reasons text can't be read as a Point --*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePointError {
+    /*-- Display form without matching { } --*/
+    MissingBraces,
+    /*-- wrong number of fields, holds number found --*/
+    FieldCount(usize),
+    /*-- a field's text could not be converted --*/
+    BadField { field: Field, text: String },
+}
+impl ParsePointError {
+    /*-- the field that failed, if the failure is in one field --*/
+    pub fn field(&self) -> Option<Field> {
+        match self {
+            ParsePointError::BadField { field, .. } => Some(*field),
+            _ => None,
+        }
+    }
+    pub(crate) fn bad(field: Field, text: &str) -> ParsePointError {
+        ParsePointError::BadField { field, text: text.to_string() }
+    }
+}
+impl Display for ParsePointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParsePointError::MissingBraces => write!(f, "point text must be enclosed in {{ }}"),
+            ParsePointError::FieldCount(n) => write!(f, "expected 4 or 5 fields, found {}", n),
+            ParsePointError::BadField { field, text } => {
+                write!(f, "can't parse {} from {:?}", field, text)
+            }
+        }
+    }
+}
+impl std::error::Error for ParsePointError {}
+
+/*-----------------------------------------------------------
+  split a CSV line on delim
+  - fields in double quotes may hold delim, "" is a quote
+  - an unterminated quote is an error holding the index
+    of the field it starts
+*/
+pub(crate) fn split_csv(line: &str, delim: char) -> Result<Vec<String>, usize> {
+    let mut fields = Vec::new();
+    let mut cur = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    cur.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            } else {
+                cur.push(c);
+            }
+        } else if c == '"' && cur.trim().is_empty() {
+            cur.clear();
+            quoted = true;
+        } else if c == delim {
+            fields.push(std::mem::take(&mut cur));
+        } else {
+            cur.push(c);
+        }
+    }
+    if quoted {
+        return Err(fields.len());
+    }
+    fields.push(cur);
+    Ok(fields)
+}
+/*-- reverse of the {:?} escaping used for names in Display form --*/
+fn unescape_debug(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            '0' => out.push('\0'),
+            '\\' => out.push('\\'),
+            '"' => out.push('"'),
+            '\'' => out.push('\''),
+            'u' => {
+                let rest = chars.as_str().strip_prefix('{')?;
+                let end = rest.find('}')?;
+                let code = u32::from_str_radix(&rest[..end], 16).ok()?;
+                out.push(std::char::from_u32(code)?);
+                chars = rest[end + 1..].chars();
+            }
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+/*-- build a point from coordinate, time, and name text --*/
+fn from_fields<T>(f: &[&str], name: String) -> Result<Point<T>, ParsePointError>
+where T: Default + Debug + Clone + FromStr {
+    let coor = |i: usize, field: Field| -> Result<T, ParsePointError> {
+        f[i].trim().parse::<T>().map_err(|_| ParsePointError::bad(field, f[i].trim()))
+    };
+    let (x, y, z) = (coor(0, Field::X)?, coor(1, Field::Y)?, coor(2, Field::Z)?);
+    let t = parse_time(f[3].trim()).ok_or_else(|| ParsePointError::bad(Field::Time, f[3].trim()))?;
+    Ok(Point { x, y, z, t, n: name })
+}
+
+impl<T> Point<T> where T: Default + Debug + Clone + FromStr {
+    /*-- { x, y, z, time, "name" } as written by Display --*/
+    pub fn from_display_str(s: &str) -> Result<Point<T>, ParsePointError> {
+        let body = s.trim()
+            .strip_prefix('{')
+            .and_then(|b| b.strip_suffix('}'))
+            .ok_or(ParsePointError::MissingBraces)?;
+        /*-- numbers and times hold no commas, the name may --*/
+        let f: Vec<&str> = body.splitn(5, ',').collect();
+        if f.len() != 5 {
+            return Err(ParsePointError::FieldCount(f.len()));
+        }
+        let name = unescape_debug(f[4].trim())
+            .ok_or_else(|| ParsePointError::bad(Field::Name, f[4].trim()))?;
+        from_fields(&f, name)
+    }
+    /*-- x,y,z,time[,name] --*/
+    pub fn from_csv(line: &str) -> Result<Point<T>, ParsePointError> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        const ORDER: [Field; 5] = [Field::X, Field::Y, Field::Z, Field::Time, Field::Name];
+        let f = split_csv(line, ',')
+            .map_err(|i| ParsePointError::bad(ORDER[i.min(4)], line))?;
+        if f.len() != 4 && f.len() != 5 {
+            return Err(ParsePointError::FieldCount(f.len()));
+        }
+        let refs: Vec<&str> = f.iter().map(|s| s.as_str()).collect();
+        let name = f.get(4).cloned().unwrap_or_default();
+        from_fields(&refs, name)
+    }
+}
+/*-- implement FromStr trait --*/
+impl<T> FromStr for Point<T> where T: Default + Debug + Clone + FromStr {
+    type Err = ParsePointError;
+    fn from_str(s: &str) -> Result<Point<T>, ParsePointError> {
+        if s.trim_start().starts_with('{') {
+            Point::from_display_str(s)
+        } else {
+            Point::from_csv(s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpaceTime;
+    #[test]
+    fn display_round_trip() {
+        let mut pt = Point::<f64>::new();
+        pt.set_coordinates(&[1.5, -2.0, 3.25]);
+        pt.set_name("a \"quoted\", name\twith ü");
+        let rt: Point<f64> = pt.to_string().parse().unwrap();
+        assert_eq!(rt, pt);
+        let mut ip = Point::<i32>::new();
+        ip.set_coordinates(&[1, 2, 3]);
+        let rt: Point<i32> = ip.to_string().parse().unwrap();
+        assert_eq!(rt, ip);
+    }
+    #[test]
+    fn csv_lines() {
+        let pt: Point<f64> = "1.5,-2,3.25,2020-06-24T13:05:09Z,\"pt, one\"\n".parse().unwrap();
+        assert_eq!(pt.get_coordinates(), [1.5, -2.0, 3.25]);
+        assert_eq!(pt.get_name(), "pt, one");
+        assert_eq!(pt.get_time_string(), "2020-06-24T13:05:09Z");
+        let pt: Point<i64> = "1,2,3,20200624T130509.5Z".parse().unwrap();
+        assert_eq!(pt.get_time_string(), "2020-06-24T13:05:09.500Z");
+        assert_eq!(pt.get_name(), "");
+    }
+    #[test]
+    fn errors_name_field() {
+        let e = "1,two,3,2020-06-24T13:05:09Z".parse::<Point<i32>>().unwrap_err();
+        assert_eq!(e.field(), Some(Field::Y));
+        let e = "{ 1, 2, 3, yesterday, \"pt\" }".parse::<Point<i32>>().unwrap_err();
+        assert_eq!(e.field(), Some(Field::Time));
+        let e = "{ 1, 2, 3, 2020-06-24T13:05:09Z, pt }".parse::<Point<i32>>().unwrap_err();
+        assert_eq!(e.field(), Some(Field::Name));
+        let e = "1,\"2,3,2020-06-24T13:05:09Z".parse::<Point<i32>>().unwrap_err();
+        assert_eq!(e.field(), Some(Field::Y));
+        let e = "1,2,3,2020-06-24T13:05:09Z,\"pt".parse::<Point<i32>>().unwrap_err();
+        assert_eq!(e.field(), Some(Field::Name));
+        assert_eq!("1,2".parse::<Point<i32>>().unwrap_err(), ParsePointError::FieldCount(2));
+        assert_eq!("{ 1, 2".parse::<Point<i32>>().unwrap_err(), ParsePointError::MissingBraces);
+    }
+}
@@ -0,0 +1,568 @@
+/////////////////////////////////////////////////////////////
+// point::point_io.rs - CSV and NDJSON streams of points   //
+/////////////////////////////////////////////////////////////
+/*
+   Streaming readers and writers for batches of Point<T>.
+   - CsvWriter / CsvReader: one point per line.  Columns may
+     be in any order, the header row is optional, and the
+     delimiter is configurable.
+   - NdjsonWriter / NdjsonReader: one flat JSON object per
+     line, e.g.,
+       {"x":1.5,"y":-2,"z":3.25,"time":"2020-06-24T13:05:09Z","name":"pt"}
+   Both writers take any Iterator<Item = Point<T>> and write
+   the time in the layout chosen by PointFormat.  Both
+   readers are iterators of Result<Point<T>, ReadError> and
+   accept time in any layout that TimeFormat writes.
+
+   Readers match columns to fields by header names when a
+   CSV header is present, by key for NDJSON, and otherwise
+   by the configured column order.  A missing name is read
+   as "".  Missing coordinates or time are errors.
+*/
+use super::parse::{split_csv, Field, ParsePointError};
+use super::time_format::parse_time;
+use super::{Point, SpaceTime, TimeFormat};
+use std::fmt::{self, Debug, Display, Write as FmtWrite};
+use std::io::{self, BufRead, Write};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/*-----------------------------------------------------------
+  options shared by readers and writers
+  - defaults: columns x, y, z, time, name; header row on;
+    comma delimiter; RFC-3339 time
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointFormat {
+    columns: Vec<Field>,
+    header: bool,
+    delimiter: char,
+    time_format: TimeFormat,
+}
+impl Default for PointFormat {
+    fn default() -> PointFormat {
+        PointFormat::new()
+    }
+}
+impl PointFormat {
+    pub fn new() -> PointFormat {
+        PointFormat {
+            columns: vec![Field::X, Field::Y, Field::Z, Field::Time, Field::Name],
+            header: true,
+            delimiter: ',',
+            time_format: TimeFormat::default(),
+        }
+    }
+    /*-- order of columns or keys, fields left out are not written --*/
+    pub fn columns(mut self, columns: &[Field]) -> PointFormat {
+        self.columns = columns.to_vec();
+        self
+    }
+    /*-- CSV only, write and expect a header row --*/
+    pub fn header(mut self, header: bool) -> PointFormat {
+        self.header = header;
+        self
+    }
+    /*-- CSV only --*/
+    pub fn delimiter(mut self, delimiter: char) -> PointFormat {
+        self.delimiter = delimiter;
+        self
+    }
+    pub fn time_format(mut self, time_format: TimeFormat) -> PointFormat {
+        self.time_format = time_format;
+        self
+    }
+}
+
+/*-- failures while reading a stream of points --*/
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    /*-- line could not be split into fields --*/
+    Syntax { line: usize, message: String },
+    /*-- a field could not be converted --*/
+    Parse { line: usize, error: ParsePointError },
+}
+impl Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "i/o error: {}", e),
+            ReadError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            ReadError::Parse { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+impl std::error::Error for ReadError {}
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> ReadError {
+        ReadError::Io(e)
+    }
+}
+
+/*-- text of one field of pt --*/
+fn field_text<T>(pt: &Point<T>, field: Field, fmt: &TimeFormat) -> String
+where T: Default + Debug + Clone + Display {
+    match field {
+        Field::X => pt.x.to_string(),
+        Field::Y => pt.y.to_string(),
+        Field::Z => pt.z.to_string(),
+        Field::Time => pt.format_time(*fmt),
+        Field::Name => pt.n.clone(),
+    }
+}
+fn field_by_name(name: &str) -> Option<Field> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "x" => Some(Field::X),
+        "y" => Some(Field::Y),
+        "z" => Some(Field::Z),
+        "time" | "t" => Some(Field::Time),
+        "name" | "n" => Some(Field::Name),
+        _ => None,
+    }
+}
+/*-- build a point from (field, text) pairs --*/
+fn assemble<T>(pairs: Vec<(Field, String)>) -> Result<Point<T>, ParsePointError>
+where T: Default + Debug + Clone + FromStr {
+    let mut slots: [Option<String>; 5] = Default::default();
+    for (f, text) in pairs {
+        slots[f as usize] = Some(text);
+    }
+    let mut coor = Vec::with_capacity(3);
+    for &f in &[Field::X, Field::Y, Field::Z] {
+        let text = slots[f as usize].as_deref().unwrap_or("").trim();
+        coor.push(text.parse::<T>().map_err(|_| ParsePointError::bad(f, text))?);
+    }
+    let text = slots[Field::Time as usize].as_deref().unwrap_or("").trim();
+    let t = parse_time(text).ok_or_else(|| ParsePointError::bad(Field::Time, text))?;
+    let mut pt = Point::<T>::new();
+    pt.set_coordinates(&[coor[0].clone(), coor[1].clone(), coor[2].clone()]);
+    pt.set_time(t);
+    pt.n = slots[Field::Name as usize].take().unwrap_or_default();
+    Ok(pt)
+}
+
+/*-----------------------------------------------------------
+  CsvWriter writes points as delimited text
+*/
+pub struct CsvWriter<W: Write> {
+    out: W,
+    format: PointFormat,
+    header_done: bool,
+}
+impl<W: Write> CsvWriter<W> {
+    pub fn new(out: W, format: PointFormat) -> CsvWriter<W> {
+        let header_done = !format.header;
+        CsvWriter { out, format, header_done }
+    }
+    /*-- quote text holding the delimiter, quotes, or line breaks --*/
+    fn quote(&self, text: &str) -> String {
+        let special = |c: char| c == self.format.delimiter || c == '"' || c == '\n' || c == '\r';
+        if text.contains(special) || text.trim() != text {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text.to_string()
+        }
+    }
+    fn write_line(&mut self, fields: Vec<String>) -> io::Result<()> {
+        let delim = self.format.delimiter.to_string();
+        writeln!(self.out, "{}", fields.join(&delim))
+    }
+    pub fn write<T>(&mut self, pt: &Point<T>) -> io::Result<()>
+    where T: Default + Debug + Clone + Display {
+        if !self.header_done {
+            let names = self.format.columns.iter().map(|f| f.to_string()).collect();
+            self.write_line(names)?;
+            self.header_done = true;
+        }
+        let fields = self.format.columns.iter()
+            .map(|f| self.quote(&field_text(pt, *f, &self.format.time_format)))
+            .collect();
+        self.write_line(fields)
+    }
+    /*-- write every point of an iterator, returns number written --*/
+    pub fn write_all<T, I>(&mut self, pts: I) -> io::Result<usize>
+    where T: Default + Debug + Clone + Display, I: IntoIterator<Item = Point<T>> {
+        let mut count = 0;
+        for pt in pts {
+            self.write(&pt)?;
+            count += 1;
+        }
+        self.out.flush()?;
+        Ok(count)
+    }
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/*-----------------------------------------------------------
+  CsvReader yields one point per non-empty line
+*/
+pub struct CsvReader<R: BufRead, T> {
+    input: R,
+    delimiter: char,
+    columns: Vec<Option<Field>>,
+    header_done: bool,
+    line: usize,
+    phantom: PhantomData<T>,
+}
+impl<R: BufRead, T> CsvReader<R, T> {
+    pub fn new(input: R, format: PointFormat) -> CsvReader<R, T> {
+        CsvReader {
+            input,
+            delimiter: format.delimiter,
+            columns: format.columns.iter().map(|f| Some(*f)).collect(),
+            header_done: !format.header,
+            line: 0,
+            phantom: PhantomData,
+        }
+    }
+    /*-- next line, without line ending, None at end of input --*/
+    fn read_line(&mut self) -> Option<io::Result<String>> {
+        let mut buf = String::new();
+        match self.input.read_line(&mut buf) {
+            Ok(0) => None,
+            Ok(_) => {
+                self.line += 1;
+                Some(Ok(buf.trim_end_matches(['\r', '\n']).to_string()))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+    /*-----------------------------------------------------
+      fields of the next non-empty record
+      - a quoted field may span lines, e.g., a name with
+        a line break in it
+    */
+    fn next_record(&mut self) -> Option<Result<Vec<String>, ReadError>> {
+        let mut text = loop {
+            match self.read_line()? {
+                Ok(text) if text.trim().is_empty() => continue,
+                Ok(text) => break text,
+                Err(e) => return Some(Err(e.into())),
+            }
+        };
+        let start = self.line;
+        loop {
+            if let Ok(fields) = split_csv(&text, self.delimiter) {
+                return Some(Ok(fields));
+            }
+            match self.read_line() {
+                Some(Ok(more)) => {
+                    text.push('\n');
+                    text.push_str(&more);
+                }
+                Some(Err(e)) => return Some(Err(e.into())),
+                None => {
+                    let message = "unterminated quote".to_string();
+                    return Some(Err(ReadError::Syntax { line: start, message }));
+                }
+            }
+        }
+    }
+}
+impl<R: BufRead, T> Iterator for CsvReader<R, T>
+where T: Default + Debug + Clone + FromStr {
+    type Item = Result<Point<T>, ReadError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.header_done {
+            self.header_done = true;
+            /*-- header names override configured order, unknown columns skipped --*/
+            match self.next_record()? {
+                Ok(names) => self.columns = names.iter().map(|n| field_by_name(n)).collect(),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        let fields = match self.next_record()? {
+            Ok(fields) => fields,
+            Err(e) => return Some(Err(e)),
+        };
+        let line = self.line;
+        let pairs = self.columns.iter()
+            .zip(fields)
+            .filter_map(|(f, text)| f.map(|f| (f, text)))
+            .collect();
+        Some(assemble(pairs).map_err(|error| ReadError::Parse { line, error }))
+    }
+}
+
+/*-----------------------------------------------------------
+  NdjsonWriter writes one JSON object per point
+*/
+pub struct NdjsonWriter<W: Write> {
+    out: W,
+    format: PointFormat,
+}
+/*-- JSON string literal --*/
+pub(crate) fn json_string(text: &str) -> String {
+    let mut s = String::with_capacity(text.len() + 2);
+    s.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(s, "\\u{:04x}", c as u32);
+            }
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+/*-- numbers that JSON can hold are written bare, others as strings --*/
+fn json_number(text: &str) -> String {
+    let is_number = text.parse::<f64>().map(|v| v.is_finite()).unwrap_or(false)
+        && !text.starts_with('+')
+        && !text.ends_with('.')
+        && !text.contains(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E');
+    if is_number { text.to_string() } else { json_string(text) }
+}
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(out: W, format: PointFormat) -> NdjsonWriter<W> {
+        NdjsonWriter { out, format }
+    }
+    pub fn write<T>(&mut self, pt: &Point<T>) -> io::Result<()>
+    where T: Default + Debug + Clone + Display {
+        let members: Vec<String> = self.format.columns.iter()
+            .map(|f| {
+                let text = field_text(pt, *f, &self.format.time_format);
+                let value = match f {
+                    Field::X | Field::Y | Field::Z => json_number(&text),
+                    Field::Time | Field::Name => json_string(&text),
+                };
+                format!("\"{}\":{}", f, value)
+            })
+            .collect();
+        writeln!(self.out, "{{{}}}", members.join(","))
+    }
+    /*-- write every point of an iterator, returns number written --*/
+    pub fn write_all<T, I>(&mut self, pts: I) -> io::Result<usize>
+    where T: Default + Debug + Clone + Display, I: IntoIterator<Item = Point<T>> {
+        let mut count = 0;
+        for pt in pts {
+            self.write(&pt)?;
+            count += 1;
+        }
+        self.out.flush()?;
+        Ok(count)
+    }
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/*-----------------------------------------------------------
+  scan one flat JSON object into (key, value text) pairs
+  - values may be strings, numbers, true, false, or null
+  - nested objects and arrays are rejected
+*/
+fn scan_object(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut chars = text.trim().chars().peekable();
+    let mut pairs = Vec::new();
+    fn skip_ws(chars: &mut std::iter::Peekable<std::str::Chars>) {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    }
+    fn string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+        let mut s = String::new();
+        loop {
+            match chars.next().ok_or("unterminated string")? {
+                '"' => return Ok(s),
+                '\\' => match chars.next().ok_or("unterminated string")? {
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'u' => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        let mut code = u32::from_str_radix(&hex, 16).map_err(|_| "bad \\u escape")?;
+                        /*-- surrogate pair --*/
+                        if (0xD800..0xDC00).contains(&code) {
+                            let rest: String = chars.by_ref().take(6).collect();
+                            let low = rest.strip_prefix("\\u")
+                                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                                .filter(|low| (0xDC00..0xE000).contains(low))
+                                .ok_or("bad surrogate pair")?;
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        }
+                        s.push(std::char::from_u32(code).ok_or("bad \\u escape")?);
+                    }
+                    c => s.push(c),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+    if chars.next() != Some('{') {
+        return Err("expected '{'".to_string());
+    }
+    skip_ws(&mut chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+    } else {
+        loop {
+            skip_ws(&mut chars);
+            if chars.next() != Some('"') {
+                return Err("expected key".to_string());
+            }
+            let key = string(&mut chars)?;
+            skip_ws(&mut chars);
+            if chars.next() != Some(':') {
+                return Err(format!("expected ':' after \"{}\"", key));
+            }
+            skip_ws(&mut chars);
+            let value = match chars.peek() {
+                Some('"') => {
+                    chars.next();
+                    string(&mut chars)?
+                }
+                Some('{') | Some('[') => return Err(format!("nested value for \"{}\"", key)),
+                _ => {
+                    let mut v = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c == ',' || c == '}' || c.is_whitespace() {
+                            break;
+                        }
+                        v.push(c);
+                        chars.next();
+                    }
+                    v
+                }
+            };
+            pairs.push((key, value));
+            skip_ws(&mut chars);
+            match chars.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err("expected ',' or '}'".to_string()),
+            }
+        }
+    }
+    skip_ws(&mut chars);
+    if chars.next().is_some() {
+        return Err("text after object".to_string());
+    }
+    Ok(pairs)
+}
+
+/*-----------------------------------------------------------
+  NdjsonReader yields one point per non-empty line
+  - keys are matched by name, unknown keys are ignored
+*/
+pub struct NdjsonReader<R: BufRead, T> {
+    input: R,
+    line: usize,
+    phantom: PhantomData<T>,
+}
+impl<R: BufRead, T> NdjsonReader<R, T> {
+    pub fn new(input: R) -> NdjsonReader<R, T> {
+        NdjsonReader { input, line: 0, phantom: PhantomData }
+    }
+}
+impl<R: BufRead, T> Iterator for NdjsonReader<R, T>
+where T: Default + Debug + Clone + FromStr {
+    type Item = Result<Point<T>, ReadError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = loop {
+            let mut buf = String::new();
+            match self.input.read_line(&mut buf) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    self.line += 1;
+                    if !buf.trim().is_empty() {
+                        break buf;
+                    }
+                }
+                Err(e) => return Some(Err(e.into())),
+            }
+        };
+        let line = self.line;
+        let pairs = match scan_object(&text) {
+            Ok(pairs) => pairs,
+            Err(message) => return Some(Err(ReadError::Syntax { line, message })),
+        };
+        let pairs = pairs.into_iter()
+            .filter_map(|(k, v)| field_by_name(&k).map(|f| (f, v)))
+            .collect();
+        Some(assemble(pairs).map_err(|error| ReadError::Parse { line, error }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Precision;
+    use chrono::{Duration, TimeZone, Utc};
+    fn points() -> Vec<Point<f64>> {
+        let t0 = Utc.timestamp_opt(1_593_003_909, 0).unwrap();
+        (0..3).map(|i| {
+            let mut p = Point::<f64>::new();
+            p.set_coordinates(&[i as f64, 0.5 * i as f64, -1.25]);
+            p.set_time(t0 + Duration::milliseconds(250 * i));
+            p.set_name(&format!("pt {}, \"{}\"\nline 2", i, i));
+            p
+        }).collect()
+    }
+    #[test]
+    fn csv_round_trip() {
+        let fmt = PointFormat::new()
+            .columns(&[Field::Time, Field::Name, Field::Z, Field::X, Field::Y])
+            .time_format(TimeFormat::UnixSeconds(Precision::Millis));
+        let mut w = CsvWriter::new(Vec::new(), fmt.clone());
+        assert_eq!(w.write_all(points()).unwrap(), 3);
+        let text = String::from_utf8(w.into_inner().unwrap()).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("time,name,z,x,y"));
+        assert_eq!(lines.next(), Some("1593003909.000,\"pt 0, \"\"0\"\""));
+        assert_eq!(lines.next(), Some("line 2\",-1.25,0,0"));
+        /*-- header row lets reader use default column order --*/
+        let rd = CsvReader::<_, f64>::new(text.as_bytes(), PointFormat::new());
+        let rt: Vec<Point<f64>> = rd.map(|r| r.unwrap()).collect();
+        assert_eq!(rt, points());
+    }
+    #[test]
+    fn csv_without_header() {
+        let fmt = PointFormat::new().header(false).delimiter(';').columns(&[Field::X, Field::Y, Field::Z, Field::Time]);
+        let mut w = CsvWriter::new(Vec::new(), fmt.clone());
+        w.write_all(points()).unwrap();
+        let bytes = w.into_inner().unwrap();
+        let rt: Vec<Point<f64>> = CsvReader::new(&bytes[..], fmt).map(|r| r.unwrap()).collect();
+        assert_eq!(rt.len(), 3);
+        assert_eq!(rt[2].get_coordinates(), points()[2].get_coordinates());
+        assert_eq!(rt[2].get_name(), "");
+        let bad = "1;2;x;2020-06-24T13:05:09Z\n";
+        let fmt = PointFormat::new().header(false).delimiter(';');
+        match CsvReader::<_, f64>::new(bad.as_bytes(), fmt).next() {
+            Some(Err(ReadError::Parse { line: 1, error })) => assert_eq!(error.field(), Some(Field::Z)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+    #[test]
+    fn ndjson_round_trip() {
+        let mut w = NdjsonWriter::new(Vec::new(), PointFormat::new());
+        w.write_all(points()).unwrap();
+        let text = String::from_utf8(w.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            text.lines().nth(1),
+            Some("{\"x\":1,\"y\":0.5,\"z\":-1.25,\"time\":\"2020-06-24T13:05:09.250Z\",\"name\":\"pt 1, \\\"1\\\"\\nline 2\"}")
+        );
+        let rt: Vec<Point<f64>> = NdjsonReader::new(text.as_bytes()).map(|r| r.unwrap()).collect();
+        assert_eq!(rt, points());
+        let odd = "{ \"name\": \"\\u00e9\\ud83d\\ude00\", \"extra\": true, \"time\": 0, \"z\": 3, \"y\": 2, \"x\": 1 }";
+        let pt: Point<i32> = NdjsonReader::new(odd.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(pt.get_coordinates(), [1, 2, 3]);
+        assert_eq!(pt.get_name(), "é😀");
+        /*-- high surrogate must be followed by a low one --*/
+        let bad = "{\"name\":\"\\ud83d\\u0041\",\"x\":1,\"y\":2,\"z\":3,\"time\":0}";
+        assert!(NdjsonReader::<_, i32>::new(bad.as_bytes()).next().unwrap().is_err());
+        assert!(scan_object(bad).unwrap_err().contains("surrogate"));
+    }
+}
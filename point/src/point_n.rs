@@ -0,0 +1,261 @@
+/////////////////////////////////////////////////////////////
+// point::point_n.rs - N-dimensional const-generic point   //
+/////////////////////////////////////////////////////////////
+/*
+   PointN<T, N> holds N coordinates in an array, so its size
+   is known at compile time and it needs no heap allocation
+   for coordinates.  PointN<f64, 2> serves drawing, PointN<f64, 4>
+   holds homogeneous coordinates for 4x4 transforms.
+   - SpaceTimeN<T, N> is the N-dimensional form of SpaceTime.
+   - Coordinates can be indexed: pt[0], pt[1], ...
+   - Add, Sub, Neg, Mul<T>, dot, and, for floating point T,
+     norm and distance work for every N.
+   - From converts between Point<T> and PointN<T, 3>.
+   - to_homogeneous / from_homogeneous convert between
+     PointN<T, 3> and PointN<T, 4>.
+*/
+use super::{Point, TimeFormat};
+use chrono::{DateTime, Utc};
+use num_traits::{Float, Num};
+use std::fmt::*;
+use std::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+
+/*-----------------------------------------------------------
+  declare SpaceTimeN<T, N> trait
+  - same as SpaceTime, with N coordinates
+*/
+pub trait SpaceTimeN<T, const N: usize> {
+    fn get_coordinates(&self) -> [T; N];
+    fn set_coordinates(&mut self, coor: &[T; N]);
+    fn set_time(&mut self, st: DateTime<Utc>);
+    fn get_time(&self) -> DateTime<Utc>;
+    fn get_time_string(&self) -> String {
+        self.format_time(TimeFormat::default())
+    }
+    fn format_time(&self, fmt: TimeFormat) -> String {
+        fmt.format(&self.get_time())
+    }
+}
+/*-- define PointN<T, N> type --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointN<T, const N: usize>
+where T: Default + Debug {
+    c: [T; N], t: DateTime<Utc>, n: String,
+}
+/*-- implement SpaceTimeN trait --*/
+impl<T, const N: usize> SpaceTimeN<T, N> for PointN<T, N>
+where T: Default + Debug + Clone {
+    fn get_coordinates(&self) -> [T; N] {
+        self.c.clone()
+    }
+    fn set_coordinates(&mut self, coor: &[T; N]) {
+        self.c = coor.clone();
+    }
+    fn set_time(&mut self, st: DateTime<Utc>) {
+        self.t = st;
+    }
+    fn get_time(&self) -> DateTime<Utc> {
+        self.t
+    }
+}
+/*-- implement Display trait, same layout as Point --*/
+impl<T, const N: usize> Display for PointN<T, N>
+where T: Default + Debug + Clone {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{{ ")?;
+        for v in self.c.iter() {
+            write!(f, "{:?}, ", v)?;
+        }
+        write!(f, "{}, {:?} }}", self.get_time_string(), &self.n)
+    }
+}
+impl<T, const N: usize> Default for PointN<T, N>
+where T: Default + Debug + Clone {
+    fn default() -> PointN<T, N> {
+        PointN::new()
+    }
+}
+/*-- implement PointN methods --*/
+impl<T, const N: usize> PointN<T, N> where T: Default + Debug + Clone {
+    pub fn new() -> PointN<T, N> {
+        PointN {
+            c: std::array::from_fn(|_| T::default()),
+            t: Utc::now(),
+            n: String::default(),
+        }
+    }
+    /*-- number of coordinates --*/
+    pub const fn dim(&self) -> usize {
+        N
+    }
+    pub fn set_name(&mut self, name: &str) {
+        self.n = name.to_string();
+    }
+    pub fn get_name(&self) -> &str {
+        &self.n
+    }
+    /*-- same time and name, new coordinates --*/
+    fn with_coords(&self, c: [T; N]) -> PointN<T, N> {
+        PointN { c, t: self.t, n: self.n.clone() }
+    }
+}
+/*-- index coordinates, panics if i >= N --*/
+impl<T, const N: usize> Index<usize> for PointN<T, N>
+where T: Default + Debug {
+    type Output = T;
+    fn index(&self, i: usize) -> &T {
+        &self.c[i]
+    }
+}
+impl<T, const N: usize> IndexMut<usize> for PointN<T, N>
+where T: Default + Debug {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        &mut self.c[i]
+    }
+}
+
+/*-- operators, result carries time and name of left operand --*/
+impl<T, const N: usize> Add for &PointN<T, N>
+where T: Default + Debug + Clone + Add<Output = T> {
+    type Output = PointN<T, N>;
+    fn add(self, rhs: &PointN<T, N>) -> PointN<T, N> {
+        self.with_coords(std::array::from_fn(|i| self.c[i].clone() + rhs.c[i].clone()))
+    }
+}
+impl<T, const N: usize> Sub for &PointN<T, N>
+where T: Default + Debug + Clone + Sub<Output = T> {
+    type Output = PointN<T, N>;
+    fn sub(self, rhs: &PointN<T, N>) -> PointN<T, N> {
+        self.with_coords(std::array::from_fn(|i| self.c[i].clone() - rhs.c[i].clone()))
+    }
+}
+impl<T, const N: usize> Mul<T> for &PointN<T, N>
+where T: Default + Debug + Clone + Mul<Output = T> {
+    type Output = PointN<T, N>;
+    fn mul(self, s: T) -> PointN<T, N> {
+        self.with_coords(std::array::from_fn(|i| self.c[i].clone() * s.clone()))
+    }
+}
+impl<T, const N: usize> Neg for &PointN<T, N>
+where T: Default + Debug + Clone + Neg<Output = T> {
+    type Output = PointN<T, N>;
+    fn neg(self) -> PointN<T, N> {
+        self.with_coords(std::array::from_fn(|i| -self.c[i].clone()))
+    }
+}
+impl<T, const N: usize> Add for PointN<T, N>
+where T: Default + Debug + Clone + Add<Output = T> {
+    type Output = PointN<T, N>;
+    fn add(self, rhs: PointN<T, N>) -> PointN<T, N> {
+        &self + &rhs
+    }
+}
+impl<T, const N: usize> Sub for PointN<T, N>
+where T: Default + Debug + Clone + Sub<Output = T> {
+    type Output = PointN<T, N>;
+    fn sub(self, rhs: PointN<T, N>) -> PointN<T, N> {
+        &self - &rhs
+    }
+}
+impl<T, const N: usize> Mul<T> for PointN<T, N>
+where T: Default + Debug + Clone + Mul<Output = T> {
+    type Output = PointN<T, N>;
+    fn mul(self, s: T) -> PointN<T, N> {
+        &self * s
+    }
+}
+impl<T, const N: usize> Neg for PointN<T, N>
+where T: Default + Debug + Clone + Neg<Output = T> {
+    type Output = PointN<T, N>;
+    fn neg(self) -> PointN<T, N> {
+        -&self
+    }
+}
+
+/*-- vector operations --*/
+impl<T, const N: usize> PointN<T, N> where T: Default + Debug + Clone + Num {
+    pub fn dot(&self, other: &PointN<T, N>) -> T {
+        self.c.iter().zip(other.c.iter())
+            .fold(T::zero(), |acc, (a, b)| acc + a.clone() * b.clone())
+    }
+}
+impl<T, const N: usize> PointN<T, N> where T: Default + Debug + Clone + Float {
+    pub fn norm(&self) -> T {
+        self.dot(self).sqrt()
+    }
+    pub fn distance(&self, other: &PointN<T, N>) -> T {
+        (self - other).norm()
+    }
+}
+
+/*-- homogeneous coordinates --*/
+impl<T> PointN<T, 3> where T: Default + Debug + Clone + Num {
+    /*-- [x, y, z] -> [x, y, z, 1] --*/
+    pub fn to_homogeneous(&self) -> PointN<T, 4> {
+        let [x, y, z] = self.c.clone();
+        PointN { c: [x, y, z, T::one()], t: self.t, n: self.n.clone() }
+    }
+    /*-- [x, y, z, w] -> [x/w, y/w, z/w], None when w is zero --*/
+    pub fn from_homogeneous(h: &PointN<T, 4>) -> Option<PointN<T, 3>> {
+        let [x, y, z, w] = h.c.clone();
+        if w == T::zero() {
+            return None;
+        }
+        Some(PointN { c: [x / w.clone(), y / w.clone(), z / w], t: h.t, n: h.n.clone() })
+    }
+}
+
+/*-- conversions to and from the 3-D Point --*/
+impl<T> From<Point<T>> for PointN<T, 3> where T: Default + Debug {
+    fn from(p: Point<T>) -> PointN<T, 3> {
+        PointN { c: [p.x, p.y, p.z], t: p.t, n: p.n }
+    }
+}
+impl<T> From<PointN<T, 3>> for Point<T> where T: Default + Debug {
+    fn from(p: PointN<T, 3>) -> Point<T> {
+        let [x, y, z] = p.c;
+        Point { x, y, z, t: p.t, n: p.n }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpaceTime;
+    #[test]
+    fn construct_and_index() {
+        let mut p = PointN::<i32, 5>::new();
+        assert_eq!(p.get_coordinates(), [0; 5]);
+        assert_eq!(p.dim(), 5);
+        p.set_coordinates(&[1, 2, 3, 4, 5]);
+        p[4] = 50;
+        assert_eq!(p[4], 50);
+        let q = &p + &p;
+        assert_eq!(q.get_coordinates(), [2, 4, 6, 8, 100]);
+        assert_eq!(p.dot(&PointN { c: [1, 0, 0, 0, 1], t: p.t, n: String::new() }), 51);
+    }
+    #[test]
+    fn two_d_and_homogeneous() {
+        let mut a = PointN::<f64, 2>::new();
+        a.set_coordinates(&[3.0, 4.0]);
+        assert_eq!(a.norm(), 5.0);
+        assert_eq!((-(a.clone() * 2.0)).get_coordinates(), [-6.0, -8.0]);
+        let mut p = PointN::<f64, 3>::new();
+        p.set_coordinates(&[1.0, 2.0, 3.0]);
+        let mut h = p.to_homogeneous();
+        assert_eq!(h.get_coordinates(), [1.0, 2.0, 3.0, 1.0]);
+        h = &h * 2.0;
+        assert_eq!(PointN::from_homogeneous(&h).unwrap().get_coordinates(), [1.0, 2.0, 3.0]);
+    }
+    #[test]
+    fn point_conversions() {
+        let mut p = Point::<i32>::new();
+        p.set_coordinates(&[7, 8, 9]);
+        p.set_name("p");
+        let n: PointN<i32, 3> = p.clone().into();
+        assert_eq!(n.get_coordinates(), [7, 8, 9]);
+        assert_eq!(n.to_string(), p.to_string());
+        let back: Point<i32> = n.into();
+        assert_eq!(back, p);
+    }
+}
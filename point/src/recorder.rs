@@ -0,0 +1,536 @@
+/////////////////////////////////////////////////////////////
+// point::recorder.rs - Binary flight-recorder log         //
+/////////////////////////////////////////////////////////////
+/*
+   Append-only binary log of Point<T> records, built to survive
+   a crash of the recording process.
+
+   File layout, all integers little-endian:
+     header:  "PTFR", version u16, coordinate kind u8,
+              reserved u8, index interval u32, crc32 u32
+     frames:  tag u8, payload length u32, payload, crc32 u32
+              crc covers tag, length, and payload
+   Frame tags:
+     RECORD:  seconds i64, nanos u32, x, y, z, name length
+              u16, name bytes (UTF-8)
+     INDEX:   count u32, then count x (seconds i64, nanos u32,
+              file offset u64) for the records written since
+              the previous INDEX frame
+
+   FlightRecorder writes an INDEX frame, and flushes, after
+   every `interval` records and when finished or dropped.
+   interval is capped at MAX_INTERVAL so an INDEX frame never
+   exceeds the payload limit the reader enforces.
+   FlightReader yields records in file order and stops at the
+   first frame that is truncated or fails its crc, so after a
+   crash every record up to the first corrupt byte is
+   recovered.  corruption() reports where reading stopped.
+   seek_time uses INDEX frames, skipping over record payloads,
+   to find the first record at or after a time.  It assumes
+   records were appended in time order.
+*/
+use super::{Point, SpaceTime};
+use chrono::{DateTime, TimeZone, Utc};
+use std::fmt::{self, Debug, Display};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+pub const MAGIC: &[u8; 4] = b"PTFR";
+pub const VERSION: u16 = 1;
+const HEADER_LEN: u64 = 16;
+const TAG_RECORD: u8 = 1;
+const TAG_INDEX: u8 = 2;
+/*-- largest payload a reader will accept, guards against garbage lengths --*/
+const MAX_PAYLOAD: u32 = 1 << 24;
+/*-- largest index interval whose index frame, 4 + 20 per entry, fits in MAX_PAYLOAD --*/
+pub const MAX_INTERVAL: u32 = (MAX_PAYLOAD - 4) / 20;
+
+/*-----------------------------------------------------------
+  CRC-32 (IEEE 802.3), table built at compile time
+*/
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut c = 0xFFFF_FFFFu32;
+    for &b in bytes {
+        c = CRC_TABLE[((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8);
+    }
+    c ^ 0xFFFF_FFFF
+}
+
+/*-----------------------------------------------------------
+  coordinate types that can be recorded
+  - KIND is stored in the header so a reader can refuse a
+    file written with a different type
+*/
+pub trait Recordable: Sized + Default + Debug + Clone {
+    const KIND: u8;
+    const SIZE: usize;
+    fn write_le(&self, out: &mut Vec<u8>);
+    fn read_le(bytes: &[u8]) -> Self;
+}
+macro_rules! recordable {
+    ($t:ty, $kind:expr) => {
+        impl Recordable for $t {
+            const KIND: u8 = $kind;
+            const SIZE: usize = std::mem::size_of::<$t>();
+            fn write_le(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn read_le(bytes: &[u8]) -> $t {
+                let mut b = [0u8; std::mem::size_of::<$t>()];
+                b.copy_from_slice(&bytes[..Self::SIZE]);
+                <$t>::from_le_bytes(b)
+            }
+        }
+    };
+}
+recordable!(i32, 1);
+recordable!(i64, 2);
+recordable!(f32, 3);
+recordable!(f64, 4);
+
+/*-- failures opening or writing a recording --*/
+#[derive(Debug)]
+pub enum RecorderError {
+    Io(io::Error),
+    BadHeader,
+    UnsupportedVersion(u16),
+    KindMismatch { expected: u8, found: u8 },
+    NameTooLong(usize),
+}
+impl Display for RecorderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecorderError::Io(e) => write!(f, "i/o error: {}", e),
+            RecorderError::BadHeader => write!(f, "not a flight recording, or header is corrupt"),
+            RecorderError::UnsupportedVersion(v) => write!(f, "unsupported recording version {}", v),
+            RecorderError::KindMismatch { expected, found } => {
+                write!(f, "coordinate kind {} expected, file holds kind {}", expected, found)
+            }
+            RecorderError::NameTooLong(n) => write!(f, "name of {} bytes exceeds 65535", n),
+        }
+    }
+}
+impl std::error::Error for RecorderError {}
+impl From<io::Error> for RecorderError {
+    fn from(e: io::Error) -> RecorderError {
+        RecorderError::Io(e)
+    }
+}
+
+fn time_bytes(t: &DateTime<Utc>, out: &mut Vec<u8>) {
+    out.extend_from_slice(&t.timestamp().to_le_bytes());
+    out.extend_from_slice(&t.timestamp_subsec_nanos().to_le_bytes());
+}
+fn time_from(b: &[u8]) -> Option<DateTime<Utc>> {
+    let mut s = [0u8; 8];
+    s.copy_from_slice(&b[..8]);
+    let mut n = [0u8; 4];
+    n.copy_from_slice(&b[8..12]);
+    Utc.timestamp_opt(i64::from_le_bytes(s), u32::from_le_bytes(n)).single()
+}
+
+/*-----------------------------------------------------------
+  FlightRecorder appends records to a log
+*/
+pub struct FlightRecorder<W: Write, T: Recordable> {
+    /*-- None only after finish() has taken the writer --*/
+    out: Option<W>,
+    offset: u64,
+    interval: u32,
+    pending: Vec<(DateTime<Utc>, u64)>,
+    phantom: PhantomData<T>,
+}
+impl<T: Recordable> FlightRecorder<BufWriter<File>, T> {
+    /*-- create or truncate a log file --*/
+    pub fn create<P: AsRef<Path>>(path: P, interval: u32) -> Result<Self, RecorderError> {
+        FlightRecorder::new(BufWriter::new(File::create(path)?), interval)
+    }
+}
+impl<W: Write, T: Recordable> FlightRecorder<W, T> {
+    /*-- writes the header, interval is clamped to 1..=MAX_INTERVAL --*/
+    pub fn new(mut out: W, interval: u32) -> Result<Self, RecorderError> {
+        let interval = interval.clamp(1, MAX_INTERVAL);
+        let mut h = Vec::with_capacity(HEADER_LEN as usize);
+        h.extend_from_slice(MAGIC);
+        h.extend_from_slice(&VERSION.to_le_bytes());
+        h.push(T::KIND);
+        h.push(0);
+        h.extend_from_slice(&interval.to_le_bytes());
+        let crc = crc32(&h);
+        h.extend_from_slice(&crc.to_le_bytes());
+        out.write_all(&h)?;
+        Ok(FlightRecorder {
+            out: Some(out),
+            offset: HEADER_LEN,
+            interval,
+            pending: Vec::new(),
+            phantom: PhantomData,
+        })
+    }
+    fn out(&mut self) -> &mut W {
+        self.out.as_mut().expect("writer present until finish")
+    }
+    fn frame(&mut self, tag: u8, payload: &[u8]) -> io::Result<()> {
+        let mut f = Vec::with_capacity(payload.len() + 9);
+        f.push(tag);
+        f.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        f.extend_from_slice(payload);
+        let crc = crc32(&f);
+        f.extend_from_slice(&crc.to_le_bytes());
+        self.out().write_all(&f)?;
+        self.offset += f.len() as u64;
+        Ok(())
+    }
+    /*-- append one point --*/
+    pub fn append(&mut self, pt: &Point<T>) -> Result<(), RecorderError> {
+        let name = pt.get_name().as_bytes();
+        if name.len() > u16::MAX as usize {
+            return Err(RecorderError::NameTooLong(name.len()));
+        }
+        let mut p = Vec::with_capacity(14 + 3 * T::SIZE + name.len());
+        time_bytes(&pt.get_time(), &mut p);
+        for c in pt.get_coordinates().iter() {
+            c.write_le(&mut p);
+        }
+        p.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        p.extend_from_slice(name);
+        self.pending.push((pt.get_time(), self.offset));
+        self.frame(TAG_RECORD, &p)?;
+        if self.pending.len() >= self.interval as usize {
+            self.write_index()?;
+        }
+        Ok(())
+    }
+    /*-- index frame for records since the last one, then flush --*/
+    fn write_index(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return self.out().flush();
+        }
+        let mut p = Vec::with_capacity(4 + 20 * self.pending.len());
+        p.extend_from_slice(&(self.pending.len() as u32).to_le_bytes());
+        for (t, off) in &self.pending {
+            time_bytes(t, &mut p);
+            p.extend_from_slice(&off.to_le_bytes());
+        }
+        self.pending.clear();
+        self.frame(TAG_INDEX, &p)?;
+        self.out().flush()
+    }
+    /*-- push buffered records to the writer without indexing them --*/
+    pub fn flush(&mut self) -> Result<(), RecorderError> {
+        self.out().flush()?;
+        Ok(())
+    }
+    /*-- index remaining records, flush, and return the writer --*/
+    pub fn finish(mut self) -> Result<W, RecorderError> {
+        self.write_index()?;
+        Ok(self.out.take().expect("writer present until finish"))
+    }
+}
+/*-- a recorder dropped without finish() still indexes its tail --*/
+impl<W: Write, T: Recordable> Drop for FlightRecorder<W, T> {
+    fn drop(&mut self) {
+        if self.out.is_some() {
+            let _ = self.write_index();
+        }
+    }
+}
+
+/*-----------------------------------------------------------
+  FlightReader reads records back
+*/
+pub struct FlightReader<R: Read + Seek, T: Recordable> {
+    input: R,
+    offset: u64,
+    interval: u32,
+    corrupt_at: Option<u64>,
+    phantom: PhantomData<T>,
+}
+impl<T: Recordable> FlightReader<BufReader<File>, T> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RecorderError> {
+        FlightReader::new(BufReader::new(File::open(path)?))
+    }
+}
+/*-- one frame read from the log --*/
+enum Frame {
+    Record(Vec<u8>),
+    Index(Vec<u8>),
+    End,
+    Corrupt,
+}
+impl<R: Read + Seek, T: Recordable> FlightReader<R, T> {
+    /*-- validates the header, positions at the first frame --*/
+    pub fn new(mut input: R) -> Result<Self, RecorderError> {
+        let mut h = [0u8; HEADER_LEN as usize];
+        input.seek(SeekFrom::Start(0))?;
+        input.read_exact(&mut h).map_err(|_| RecorderError::BadHeader)?;
+        let crc = u32::from_le_bytes([h[12], h[13], h[14], h[15]]);
+        if &h[..4] != MAGIC || crc32(&h[..12]) != crc {
+            return Err(RecorderError::BadHeader);
+        }
+        let version = u16::from_le_bytes([h[4], h[5]]);
+        if version != VERSION {
+            return Err(RecorderError::UnsupportedVersion(version));
+        }
+        if h[6] != T::KIND {
+            return Err(RecorderError::KindMismatch { expected: T::KIND, found: h[6] });
+        }
+        let interval = u32::from_le_bytes([h[8], h[9], h[10], h[11]]);
+        Ok(FlightReader { input, offset: HEADER_LEN, interval, corrupt_at: None, phantom: PhantomData })
+    }
+    /*-- records per index frame used by the writer --*/
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+    /*-- offset of the first bad frame, once reading has reached it --*/
+    pub fn corruption(&self) -> Option<u64> {
+        self.corrupt_at
+    }
+    /*-- tag and length of frame at current offset, None at clean end --*/
+    fn frame_head(&mut self) -> Result<Option<(u8, u32, [u8; 5])>, ()> {
+        let mut head = [0u8; 5];
+        let mut got = 0;
+        while got < 5 {
+            match self.input.read(&mut head[got..]) {
+                Ok(0) if got == 0 => return Ok(None),
+                Ok(0) => return Err(()),
+                Ok(n) => got += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => return Err(()),
+            }
+        }
+        let len = u32::from_le_bytes([head[1], head[2], head[3], head[4]]);
+        if (head[0] != TAG_RECORD && head[0] != TAG_INDEX) || len > MAX_PAYLOAD {
+            return Err(());
+        }
+        Ok(Some((head[0], len, head)))
+    }
+    fn read_frame(&mut self) -> Frame {
+        if self.corrupt_at.is_some() {
+            return Frame::Corrupt;
+        }
+        let start = self.offset;
+        let result = (|| {
+            let (tag, len, head) = match self.frame_head()? {
+                Some(h) => h,
+                None => return Ok(Frame::End),
+            };
+            let mut body = vec![0u8; len as usize + 4];
+            self.input.read_exact(&mut body).map_err(|_| ())?;
+            let (payload, crc) = body.split_at(len as usize);
+            let mut check = head.to_vec();
+            check.extend_from_slice(payload);
+            if crc32(&check) != u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+                return Err(());
+            }
+            body.truncate(len as usize);
+            self.offset += 9 + len as u64;
+            Ok(if tag == TAG_RECORD { Frame::Record(body) } else { Frame::Index(body) })
+        })();
+        match result {
+            Ok(frame) => frame,
+            Err(()) => {
+                self.corrupt_at = Some(start);
+                Frame::Corrupt
+            }
+        }
+    }
+    fn decode(&self, p: &[u8]) -> Option<Point<T>> {
+        let n = T::SIZE;
+        if p.len() < 14 + 3 * n {
+            return None;
+        }
+        let t = time_from(&p[..12])?;
+        let c = [T::read_le(&p[12..]), T::read_le(&p[12 + n..]), T::read_le(&p[12 + 2 * n..])];
+        let at = 12 + 3 * n;
+        let len = u16::from_le_bytes([p[at], p[at + 1]]) as usize;
+        let name = std::str::from_utf8(p.get(at + 2..at + 2 + len)?).ok()?;
+        let mut pt = Point::<T>::new();
+        pt.set_coordinates(&c);
+        pt.set_time(t);
+        pt.set_name(name);
+        Some(pt)
+    }
+    /*-----------------------------------------------------
+      position reader at first record with time >= t
+      - returns false if there is no such record
+      - walks INDEX frames, seeking past record payloads,
+        then scans records written after the last index
+    */
+    pub fn seek_time(&mut self, t: DateTime<Utc>) -> Result<bool, RecorderError> {
+        self.offset = HEADER_LEN;
+        self.corrupt_at = None;
+        self.input.seek(SeekFrom::Start(HEADER_LEN))?;
+        let mut after_index = HEADER_LEN;
+        loop {
+            let start = self.offset;
+            let (tag, len) = match self.frame_head() {
+                Ok(Some((tag, len, _))) => (tag, len),
+                Ok(None) | Err(()) => break,
+            };
+            if tag == TAG_RECORD {
+                self.offset = self.input.seek(SeekFrom::Current(len as i64 + 4))?;
+                continue;
+            }
+            self.offset = start;
+            self.input.seek(SeekFrom::Start(start))?;
+            let p = match self.read_frame() {
+                Frame::Index(p) => p,
+                _ => break,
+            };
+            after_index = self.offset;
+            let count = u32::from_le_bytes([p[0], p[1], p[2], p[3]]) as usize;
+            for e in p[4..].chunks_exact(20).take(count) {
+                if time_from(&e[..12]).is_some_and(|et| et >= t) {
+                    let mut off = [0u8; 8];
+                    off.copy_from_slice(&e[12..20]);
+                    self.offset = u64::from_le_bytes(off);
+                    self.input.seek(SeekFrom::Start(self.offset))?;
+                    return Ok(true);
+                }
+            }
+        }
+        /*-- not in any index, scan records that follow the last index --*/
+        self.corrupt_at = None;
+        self.offset = after_index;
+        self.input.seek(SeekFrom::Start(after_index))?;
+        loop {
+            let start = self.offset;
+            match self.read_frame() {
+                Frame::Record(p) => {
+                    if self.decode(&p).is_some_and(|pt| pt.get_time() >= t) {
+                        self.offset = start;
+                        self.input.seek(SeekFrom::Start(start))?;
+                        return Ok(true);
+                    }
+                }
+                Frame::Index(_) => {}
+                Frame::End | Frame::Corrupt => return Ok(false),
+            }
+        }
+    }
+}
+/*-- yields records until end of file or first corrupt frame --*/
+impl<R: Read + Seek, T: Recordable> Iterator for FlightReader<R, T> {
+    type Item = Point<T>;
+    fn next(&mut self) -> Option<Point<T>> {
+        loop {
+            let start = self.offset;
+            match self.read_frame() {
+                Frame::Record(p) => match self.decode(&p) {
+                    Some(pt) => return Some(pt),
+                    None => {
+                        self.corrupt_at = Some(start);
+                        return None;
+                    }
+                },
+                Frame::Index(_) => continue,
+                Frame::End | Frame::Corrupt => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use std::io::Cursor;
+    fn record(n: i64, interval: u32) -> Vec<u8> {
+        let t0 = Utc.timestamp_opt(1_593_003_909, 0).unwrap();
+        let mut rec = FlightRecorder::<_, f64>::new(Vec::new(), interval).unwrap();
+        for i in 0..n {
+            let mut p = Point::<f64>::new();
+            p.set_coordinates(&[i as f64, -0.5 * i as f64, 1000.0 + i as f64]);
+            p.set_time(t0 + Duration::milliseconds(100 * i));
+            p.set_name(&format!("sample {}", i));
+            rec.append(&p).unwrap();
+        }
+        rec.finish().unwrap()
+    }
+    #[test]
+    fn crc_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+    #[test]
+    fn round_trip() {
+        let bytes = record(25, 10);
+        let rd = FlightReader::<_, f64>::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(rd.interval(), 10);
+        let pts: Vec<Point<f64>> = rd.collect();
+        assert_eq!(pts.len(), 25);
+        assert_eq!(pts[7].get_coordinates(), [7.0, -3.5, 1007.0]);
+        assert_eq!(pts[7].get_name(), "sample 7");
+        match FlightReader::<_, i32>::new(Cursor::new(record(1, 1))) {
+            Err(RecorderError::KindMismatch { expected: 1, found: 4 }) => {}
+            _ => panic!("kind mismatch not detected"),
+        }
+    }
+    #[test]
+    fn largest_interval() {
+        let rec = FlightRecorder::<_, f64>::new(Vec::new(), u32::MAX).unwrap();
+        assert_eq!(rec.interval, MAX_INTERVAL);
+        let n = MAX_INTERVAL as i64;
+        let bytes = record(n, MAX_INTERVAL);
+        let mut rd = FlightReader::<_, f64>::new(Cursor::new(bytes.clone())).unwrap();
+        assert_eq!(rd.interval(), MAX_INTERVAL);
+        let t0 = Utc.timestamp_opt(1_593_003_909, 0).unwrap();
+        assert!(rd.seek_time(t0 + Duration::milliseconds(100 * (n - 1))).unwrap());
+        assert_eq!(rd.next().unwrap().get_name(), format!("sample {}", n - 1));
+        let mut rd = FlightReader::<_, f64>::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(rd.by_ref().count(), n as usize);
+        assert!(rd.corruption().is_none());
+    }
+    #[test]
+    fn recovers_up_to_corruption() {
+        let bytes = record(25, 10);
+        /*-- crash mid-record: lose final index frame, 113 bytes, and end of last record --*/
+        let cut = bytes.len() - 113 - 10;
+        let mut rd = FlightReader::<_, f64>::new(Cursor::new(bytes[..cut].to_vec())).unwrap();
+        let n = rd.by_ref().count();
+        assert_eq!(n, 24);
+        assert!(rd.corruption().is_some());
+        /*-- flipped byte in record 12 --*/
+        let mut bad = bytes.clone();
+        let mut rd = FlightReader::<_, f64>::new(Cursor::new(bytes)).unwrap();
+        rd.seek_time(Utc.timestamp_opt(1_593_003_910, 200_000_000).unwrap()).unwrap();
+        let off = rd.offset as usize;
+        bad[off + 20] ^= 0xFF;
+        let mut rd = FlightReader::<_, f64>::new(Cursor::new(bad)).unwrap();
+        let pts: Vec<_> = rd.by_ref().collect();
+        assert_eq!(pts.len(), 12);
+        assert_eq!(rd.corruption(), Some(off as u64));
+    }
+    #[test]
+    fn seek_by_time() {
+        let t0 = Utc.timestamp_opt(1_593_003_909, 0).unwrap();
+        let mut rd = FlightReader::<_, f64>::new(Cursor::new(record(25, 10))).unwrap();
+        /*-- indexed record --*/
+        assert!(rd.seek_time(t0 + Duration::milliseconds(1250)).unwrap());
+        assert_eq!(rd.next().unwrap().get_name(), "sample 13");
+        /*-- unindexed tail, as after a crash --*/
+        let bytes = record(25, 10);
+        let cut = bytes.len() - 9 - 4 - 5 * 20;
+        let mut rd = FlightReader::<_, f64>::new(Cursor::new(bytes[..cut].to_vec())).unwrap();
+        assert!(rd.seek_time(t0 + Duration::milliseconds(2150)).unwrap());
+        assert_eq!(rd.next().unwrap().get_name(), "sample 22");
+        assert!(!rd.seek_time(t0 + Duration::seconds(60)).unwrap());
+    }
+}
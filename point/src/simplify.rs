@@ -0,0 +1,234 @@
+/////////////////////////////////////////////////////////////
+// point::simplify.rs - Thin and resample point sequences  //
+/////////////////////////////////////////////////////////////
+/*
+   Reduce dense, time-ordered point sequences:
+   - douglas_peucker: keeps the points needed so that no
+     dropped point is farther than tolerance from the
+     simplified path (Ramer-Douglas-Peucker).
+   - visvalingam: repeatedly drops the point whose triangle
+     with its neighbors has the smallest area, until every
+     remaining triangle has at least min_area
+     (Visvalingam-Whyatt).
+   Both work on 3-D position only, always keep the first and
+   last points, and return the kept points unchanged, with
+   their original times and names.
+
+   resample produces points at a fixed time step from the
+   first sample's time, interpolated with Trajectory's Linear
+   or Cubic mode.  Trajectory has matching methods.
+*/
+use super::{Interpolation, Point, SpaceTime, Trajectory};
+use chrono::Duration;
+use num_traits::Float;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt::Debug;
+
+fn sub<T: Float>(a: &[T; 3], b: &[T; 3]) -> [T; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+fn dot<T: Float>(a: &[T; 3], b: &[T; 3]) -> T {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+/*-- distance from p to segment [a, b] --*/
+fn segment_distance<T: Float>(p: &[T; 3], a: &[T; 3], b: &[T; 3]) -> T {
+    let ab = sub(b, a);
+    let ap = sub(p, a);
+    let len2 = dot(&ab, &ab);
+    let s = if len2 > T::zero() { (dot(&ap, &ab) / len2).max(T::zero()).min(T::one()) } else { T::zero() };
+    let d = [ap[0] - ab[0] * s, ap[1] - ab[1] * s, ap[2] - ab[2] * s];
+    dot(&d, &d).sqrt()
+}
+/*-- area of triangle a, b, c --*/
+fn triangle_area<T: Float>(a: &[T; 3], b: &[T; 3], c: &[T; 3]) -> T {
+    let (u, v) = (sub(b, a), sub(c, a));
+    let x = [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]];
+    dot(&x, &x).sqrt() / (T::one() + T::one())
+}
+
+/*-- Ramer-Douglas-Peucker, iterative to avoid deep recursion --*/
+pub fn douglas_peucker<T>(pts: &[Point<T>], tolerance: T) -> Vec<Point<T>>
+where T: Default + Debug + Clone + Float {
+    if pts.len() < 3 {
+        return pts.to_vec();
+    }
+    let c: Vec<[T; 3]> = pts.iter().map(|p| p.get_coordinates()).collect();
+    let mut keep = vec![false; pts.len()];
+    keep[0] = true;
+    keep[pts.len() - 1] = true;
+    let mut stack = vec![(0, pts.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let mut worst = (T::zero(), 0);
+        for i in (first + 1)..last {
+            let d = segment_distance(&c[i], &c[first], &c[last]);
+            if d > worst.0 {
+                worst = (d, i);
+            }
+        }
+        if worst.0 > tolerance {
+            keep[worst.1] = true;
+            stack.push((first, worst.1));
+            stack.push((worst.1, last));
+        }
+    }
+    pts.iter().zip(keep).filter(|(_, k)| *k).map(|(p, _)| p.clone()).collect()
+}
+
+/*-- heap entry, smallest area first, ties by index --*/
+struct Entry<T> {
+    area: T,
+    index: usize,
+    version: u32,
+}
+impl<T: Float> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl<T: Float> Eq for Entry<T> {}
+impl<T: Float> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T: Float> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.area.partial_cmp(&self.area)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+/*-----------------------------------------------------------
+  Visvalingam-Whyatt
+  - a dropped point's neighbors get new areas, never smaller
+    than the dropped area, so the result does not depend on
+    the order of removal among equal areas
+*/
+pub fn visvalingam<T>(pts: &[Point<T>], min_area: T) -> Vec<Point<T>>
+where T: Default + Debug + Clone + Float {
+    let n = pts.len();
+    if n < 3 {
+        return pts.to_vec();
+    }
+    let c: Vec<[T; 3]> = pts.iter().map(|p| p.get_coordinates()).collect();
+    let mut prev: Vec<usize> = (0..n).map(|i| i.saturating_sub(1)).collect();
+    let mut next: Vec<usize> = (0..n).map(|i| (i + 1).min(n - 1)).collect();
+    let mut version = vec![0u32; n];
+    let mut alive = vec![true; n];
+    let mut heap = BinaryHeap::new();
+    for i in 1..n - 1 {
+        heap.push(Entry { area: triangle_area(&c[i - 1], &c[i], &c[i + 1]), index: i, version: 0 });
+    }
+    let mut floor = T::zero();
+    while let Some(e) = heap.pop() {
+        if e.version != version[e.index] || !alive[e.index] {
+            continue;
+        }
+        let area = e.area.max(floor);
+        if area >= min_area {
+            break;
+        }
+        floor = area;
+        let (p, q) = (prev[e.index], next[e.index]);
+        alive[e.index] = false;
+        next[p] = q;
+        prev[q] = p;
+        for k in [p, q] {
+            if k != 0 && k != n - 1 {
+                version[k] += 1;
+                let a = triangle_area(&c[prev[k]], &c[k], &c[next[k]]);
+                heap.push(Entry { area: a, index: k, version: version[k] });
+            }
+        }
+    }
+    pts.iter().zip(alive).filter(|(_, a)| *a).map(|(p, _)| p.clone()).collect()
+}
+
+/*-- points every step from the first sample's time, empty if step <= 0 --*/
+pub fn resample<T>(pts: &[Point<T>], step: Duration, mode: Interpolation) -> Vec<Point<T>>
+where T: Default + Debug + Clone + Float {
+    Trajectory::from_points(pts.to_vec()).resample(step, mode).into_points()
+}
+
+impl<T> Trajectory<T> where T: Default + Debug + Clone + Float {
+    pub fn simplify_douglas_peucker(&self, tolerance: T) -> Trajectory<T> {
+        Trajectory::from_points(douglas_peucker(self.samples(), tolerance))
+    }
+    pub fn simplify_visvalingam(&self, min_area: T) -> Trajectory<T> {
+        Trajectory::from_points(visvalingam(self.samples(), min_area))
+    }
+    /*-- last new sample is at or before the last recorded time --*/
+    pub fn resample(&self, step: Duration, mode: Interpolation) -> Trajectory<T> {
+        let mut out = Trajectory::new();
+        let (start, end) = match (self.start_time(), self.end_time()) {
+            (Some(s), Some(e)) if step > Duration::zero() => (s, e),
+            _ => return out,
+        };
+        let mut t = start;
+        while t <= end {
+            if let Some(p) = self.position_at(t, mode) {
+                out.push(p);
+            }
+            t += step;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    fn line(t0: DateTime<Utc>, c: &[[f64; 3]]) -> Vec<Point<f64>> {
+        c.iter().enumerate().map(|(i, c)| {
+            let mut p = Point::new();
+            p.set_coordinates(c);
+            p.set_time(t0 + Duration::seconds(i as i64));
+            p.set_name(&format!("p{}", i));
+            p
+        }).collect()
+    }
+    #[test]
+    fn douglas_peucker_keeps_shape() {
+        let t0 = Utc::now();
+        let pts = line(t0, &[
+            [0.0, 0.0, 0.0], [1.0, 0.1, 0.0], [2.0, -0.1, 0.0], [3.0, 5.0, 0.0],
+            [4.0, 6.0, 0.0], [5.0, 7.0, 0.0], [6.0, 8.1, 0.0], [7.0, 9.0, 0.0],
+        ]);
+        let s = douglas_peucker(&pts, 0.5);
+        let names: Vec<&str> = s.iter().map(|p| p.get_name()).collect();
+        assert_eq!(names, ["p0", "p2", "p3", "p7"]);
+        assert_eq!(s[1].get_time(), pts[2].get_time());
+        assert_eq!(douglas_peucker(&pts, 100.0).len(), 2);
+        assert_eq!(douglas_peucker(&pts[..2], 0.0).len(), 2);
+    }
+    #[test]
+    fn visvalingam_drops_small_triangles() {
+        let t0 = Utc::now();
+        let pts = line(t0, &[
+            [0.0, 0.0, 0.0], [1.0, 0.01, 0.0], [2.0, 0.0, 0.0], [3.0, 3.0, 0.0], [4.0, 0.0, 0.0],
+        ]);
+        let s = visvalingam(&pts, 0.5);
+        let names: Vec<&str> = s.iter().map(|p| p.get_name()).collect();
+        assert_eq!(names, ["p0", "p2", "p3", "p4"]);
+        assert_eq!(visvalingam(&pts, 1.0e9).len(), 2);
+        let tr = Trajectory::from_points(pts);
+        assert_eq!(tr.simplify_visvalingam(0.0).len(), 5);
+        assert_eq!(tr.simplify_douglas_peucker(0.5).len(), 4);
+    }
+    #[test]
+    fn fixed_step_resampling() {
+        let t0 = Utc::now();
+        let pts = line(t0, &[[0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [20.0, 10.0, 0.0]]);
+        let r = resample(&pts, Duration::milliseconds(500), Interpolation::Linear);
+        assert_eq!(r.len(), 5);
+        assert_eq!(r[1].get_coordinates(), [5.0, 0.0, 0.0]);
+        assert_eq!(r[3].get_time(), t0 + Duration::milliseconds(1500));
+        assert_eq!(r[4].get_coordinates(), [20.0, 10.0, 0.0]);
+        let r = resample(&pts, Duration::milliseconds(1500), Interpolation::Cubic);
+        assert_eq!(r.len(), 2);
+        assert!(resample(&pts, Duration::zero(), Interpolation::Linear).is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+/////////////////////////////////////////////////////////////
+// point::simulator.rs - Synthetic Point<f64> streams      //
+/////////////////////////////////////////////////////////////
+/*
+   Simulator produces timestamped Point<f64> samples along a
+   Route, for tests and demos:
+   - Line:       constant velocity from a start position
+   - Circle:     constant speed around a center, in x, y
+   - Holding:    racetrack holding pattern, right turns, with
+                 the inbound leg ending at the fix
+   - RandomWalk: Gaussian steps with variance growing with
+                 elapsed time
+   Samples are taken every interval from the start time, set
+   with set_time.  Gaussian measurement noise, if any, is added
+   to each coordinate.  Headings are degrees clockwise from +y
+   (north), as in Trajectory.
+
+   All randomness comes from a SplitMix64 generator seeded by
+   the caller, so the same seed and settings always give the
+   same stream.  Simulator is an Iterator that only ends when
+   the sample time can't be represented, use take(n).
+*/
+use super::{Point, SpaceTime};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::f64::consts::PI;
+
+/*-- SplitMix64, small, fast, and fully reproducible --*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitMix64 {
+    state: u64,
+}
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+    /*-- uniform in [0, 1) --*/
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+    /*-- standard normal, Box-Muller --*/
+    pub fn gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Route {
+    /*-- units per second --*/
+    Line { start: [f64; 3], velocity: [f64; 3] },
+    /*-- period in seconds, counterclockwise, phase in degrees from +x --*/
+    Circle { center: [f64; 3], radius: f64, period: f64, phase: f64 },
+    /*-- inbound heading in degrees, speed in units per second --*/
+    Holding { fix: [f64; 3], inbound_heading: f64, leg_length: f64, turn_radius: f64, speed: f64 },
+    /*-- step standard deviation per axis after one second --*/
+    RandomWalk { start: [f64; 3], sigma: f64 },
+}
+impl Route {
+    /*-- noise-free position after secs, None for RandomWalk --*/
+    pub fn position_at(&self, secs: f64) -> Option<[f64; 3]> {
+        match *self {
+            Route::Line { start, velocity } => Some([
+                start[0] + velocity[0] * secs,
+                start[1] + velocity[1] * secs,
+                start[2] + velocity[2] * secs,
+            ]),
+            Route::Circle { center, radius, period, phase } => {
+                let a = phase.to_radians() + 2.0 * PI * secs / period;
+                Some([center[0] + radius * a.cos(), center[1] + radius * a.sin(), center[2]])
+            }
+            Route::Holding { fix, inbound_heading, leg_length, turn_radius, speed } => {
+                Some(holding(fix, inbound_heading, leg_length, turn_radius, speed * secs))
+            }
+            Route::RandomWalk { .. } => None,
+        }
+    }
+}
+/*-----------------------------------------------------------
+  position at distance s around a racetrack that starts at
+  the fix on the inbound heading: right turn, outbound leg,
+  right turn, inbound leg back to the fix
+*/
+fn holding(fix: [f64; 3], heading: f64, leg: f64, r: f64, s: f64) -> [f64; 3] {
+    let h = heading.to_radians();
+    let u = [h.sin(), h.cos()];
+    let v = [h.cos(), -h.sin()];
+    let turn = PI * r;
+    let lap = 2.0 * leg + 2.0 * turn;
+    let s = if lap > 0.0 { s.rem_euclid(lap) } else { 0.0 };
+    /*-- (along u, along v) from the fix --*/
+    let (a, b) = if s < turn {
+        let th = s / r;
+        (r * th.sin(), r - r * th.cos())
+    } else if s < turn + leg {
+        (-(s - turn), 2.0 * r)
+    } else if s < 2.0 * turn + leg {
+        let th = (s - turn - leg) / r;
+        (-leg - r * th.sin(), r + r * th.cos())
+    } else {
+        (-leg + (s - 2.0 * turn - leg), 0.0)
+    };
+    [fix[0] + a * u[0] + b * v[0], fix[1] + a * u[1] + b * v[1], fix[2]]
+}
+
+/*-- define Simulator type --*/
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    route: Route,
+    rng: SplitMix64,
+    start: DateTime<Utc>,
+    interval: Duration,
+    noise: f64,
+    name: String,
+    count: i64,
+    walk: [f64; 3],
+}
+impl Simulator {
+    /*-- one sample per second from the Unix epoch, no noise --*/
+    pub fn new(route: Route, seed: u64) -> Simulator {
+        let walk = match route {
+            Route::RandomWalk { start, .. } => start,
+            _ => [0.0; 3],
+        };
+        Simulator {
+            route,
+            rng: SplitMix64::new(seed),
+            start: Utc.timestamp_opt(0, 0).unwrap(),
+            interval: Duration::seconds(1),
+            noise: 0.0,
+            name: String::new(),
+            count: 0,
+            walk,
+        }
+    }
+    pub fn with_start(mut self, start: DateTime<Utc>) -> Simulator {
+        self.start = start;
+        self
+    }
+    pub fn with_interval(mut self, interval: Duration) -> Simulator {
+        self.interval = interval;
+        self
+    }
+    /*-- samples per second, non-positive rates are ignored --*/
+    pub fn with_rate(self, hz: f64) -> Simulator {
+        if hz > 0.0 {
+            let ns = (1.0e9 / hz).round() as i64;
+            self.with_interval(Duration::nanoseconds(ns))
+        } else {
+            self
+        }
+    }
+    /*-- standard deviation of noise added to each coordinate --*/
+    pub fn with_noise(mut self, sigma: f64) -> Simulator {
+        self.noise = sigma;
+        self
+    }
+    pub fn with_name(mut self, name: &str) -> Simulator {
+        self.name = name.to_string();
+        self
+    }
+    /*-- next (true position, measured position) pair, None once time overflows --*/
+    pub fn next_with_truth(&mut self) -> Option<(Point<f64>, Point<f64>)> {
+        let k = self.count;
+        let offset = self.interval.num_nanoseconds().and_then(|ns| ns.checked_mul(k))?;
+        let time = self.start.checked_add_signed(Duration::nanoseconds(offset))?;
+        self.count = k.checked_add(1)?;
+        let dt = self.interval.num_nanoseconds().map_or(0.0, |ns| ns as f64 * 1e-9);
+        let secs = dt * k as f64;
+        let c = match self.route {
+            Route::RandomWalk { sigma, .. } => {
+                if k > 0 {
+                    let step = sigma * dt.sqrt();
+                    for w in self.walk.iter_mut() {
+                        *w += step * self.rng.gaussian();
+                    }
+                }
+                self.walk
+            }
+            ref p => p.position_at(secs).unwrap_or_default(),
+        };
+        let mut truth = Point::new();
+        truth.set_coordinates(&c);
+        truth.set_time(time);
+        truth.set_name(&self.name);
+        let mut measured = truth.clone();
+        if self.noise > 0.0 {
+            let n = self.noise;
+            let m = [c[0] + n * self.rng.gaussian(), c[1] + n * self.rng.gaussian(), c[2] + n * self.rng.gaussian()];
+            measured.set_coordinates(&m);
+        }
+        Some((truth, measured))
+    }
+}
+impl Iterator for Simulator {
+    type Item = Point<f64>;
+    fn next(&mut self) -> Option<Point<f64>> {
+        self.next_with_truth().map(|(_, measured)| measured)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn dist(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+        ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+    }
+    #[test]
+    fn seeded_and_timed() {
+        let route = Route::Line { start: [0.0, 0.0, 1000.0], velocity: [10.0, 0.0, -1.0] };
+        let t0 = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let sim = Simulator::new(route.clone(), 7).with_start(t0).with_rate(4.0).with_noise(2.0).with_name("sim");
+        let a: Vec<Point<f64>> = sim.clone().take(20).collect();
+        let b: Vec<Point<f64>> = sim.take(20).collect();
+        assert_eq!(a, b);
+        let c: Vec<Point<f64>> = Simulator::new(route.clone(), 8).with_noise(2.0).take(20).collect();
+        assert_ne!(a[5].get_coordinates(), c[5].get_coordinates());
+        assert_eq!(a[8].get_time(), t0 + Duration::seconds(2));
+        assert_eq!(a[8].get_name(), "sim");
+        let clean: Vec<Point<f64>> = Simulator::new(route, 7).take(3).collect();
+        assert_eq!(clean[2].get_coordinates(), [20.0, 0.0, 998.0]);
+    }
+    #[test]
+    fn circles_and_holding() {
+        let circle = Route::Circle { center: [5.0, 5.0, 0.0], radius: 3.0, period: 60.0, phase: 90.0 };
+        let p = circle.position_at(15.0).unwrap();
+        assert!(dist(&p, &[2.0, 5.0, 0.0]) < 1e-9);
+        let hold = Route::Holding {
+            fix: [0.0, 0.0, 3000.0], inbound_heading: 0.0, leg_length: 100.0, turn_radius: 20.0, speed: 10.0,
+        };
+        let lap = (200.0 + 40.0 * PI) / 10.0;
+        assert!(dist(&hold.position_at(0.0).unwrap(), &[0.0, 0.0, 3000.0]) < 1e-9);
+        assert!(dist(&hold.position_at(lap).unwrap(), &[0.0, 0.0, 3000.0]) < 1e-9);
+        /*-- heading north, right turns put the pattern east of the fix --*/
+        let abeam = hold.position_at(20.0 * PI / 10.0).unwrap();
+        assert!(dist(&abeam, &[40.0, 0.0, 3000.0]) < 1e-9);
+        let mut sim = Simulator::new(hold, 1).with_interval(Duration::milliseconds(500));
+        for _ in 0..200 {
+            let c = sim.next_with_truth().unwrap().0.get_coordinates();
+            assert!(c[0] >= -1e-9 && c[0] <= 40.0 + 1e-9 && c[1] >= -120.0 - 1e-9 && c[1] <= 20.0 + 1e-9);
+        }
+    }
+    #[test]
+    fn random_walk() {
+        let route = Route::RandomWalk { start: [1.0, 2.0, 3.0], sigma: 0.5 };
+        let pts: Vec<Point<f64>> = Simulator::new(route.clone(), 42).take(400).collect();
+        assert_eq!(pts[0].get_coordinates(), [1.0, 2.0, 3.0]);
+        let steps: Vec<f64> = pts.windows(2).map(|w| w[1].get_coordinates()[0] - w[0].get_coordinates()[0]).collect();
+        let var = steps.iter().map(|s| s * s).sum::<f64>() / steps.len() as f64;
+        assert!((var - 0.25).abs() < 0.05);
+        let again: Vec<Point<f64>> = Simulator::new(route, 42).take(400).collect();
+        assert_eq!(pts[399], again[399]);
+        /*-- ends instead of overflowing --*/
+        let line = Route::Line { start: [0.0; 3], velocity: [1.0; 3] };
+        let mut sim = Simulator::new(line, 1).with_interval(Duration::days(50_000));
+        assert_eq!(sim.by_ref().count(), 3);
+        assert!(sim.next_with_truth().is_none());
+    }
+}
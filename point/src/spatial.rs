@@ -0,0 +1,279 @@
+/////////////////////////////////////////////////////////////
+// point::spatial.rs - k-d tree over SpaceTime objects     //
+/////////////////////////////////////////////////////////////
+/*
+   KdTree<S, T> indexes any collection of objects that
+   implement SpaceTime<T>, e.g., Point<f64>, by their spatial
+   coordinates.
+   - Built once from a Vec<S>, balanced by median splits.
+   - Supports nearest, k-nearest, radius, and axis-aligned box
+     queries.
+   - Every query takes an optional TimeWindow.  Only objects
+     whose get_time() falls in the window are returned.
+   Coordinates are read once, at build time, and held as f64.
+*/
+use super::SpaceTime;
+use chrono::offset::Utc;
+use chrono::DateTime;
+use num_traits::ToPrimitive;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::marker::PhantomData;
+
+/*-- closed interval of time, start <= t <= end --*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+impl TimeWindow {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> TimeWindow {
+        TimeWindow { start, end }
+    }
+    pub fn contains(&self, t: DateTime<Utc>) -> bool {
+        self.start <= t && t <= self.end
+    }
+}
+
+/*-- convert coordinate array to f64, non-numeric values become NaN --*/
+pub(crate) fn to_f64<T: ToPrimitive>(c: &[T; 3]) -> [f64; 3] {
+    let f = |v: &T| v.to_f64().unwrap_or(f64::NAN);
+    [f(&c[0]), f(&c[1]), f(&c[2])]
+}
+fn dist_sq(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3).map(|k| (a[k] - b[k]) * (a[k] - b[k])).sum()
+}
+
+#[derive(Debug)]
+struct Entry<S> {
+    coor: [f64; 3],
+    time: DateTime<Utc>,
+    item: S,
+}
+
+/*-- candidate for k-nearest, heap keeps the farthest on top --*/
+struct Candidate {
+    d2: f64,
+    idx: usize,
+}
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Candidate) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Candidate {}
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Candidate {
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        self.d2.total_cmp(&other.d2).then(self.idx.cmp(&other.idx))
+    }
+}
+
+/*-----------------------------------------------------------
+  define KdTree<S, T> type
+  - entries are stored as an implicit tree: the median of
+    each slice is the node, the halves are its subtrees
+*/
+#[derive(Debug)]
+pub struct KdTree<S, T> {
+    entries: Vec<Entry<S>>,
+    phantom: PhantomData<T>,
+}
+impl<S, T> KdTree<S, T>
+where S: SpaceTime<T>, T: ToPrimitive {
+    pub fn build(items: Vec<S>) -> KdTree<S, T> {
+        let mut entries: Vec<Entry<S>> = items
+            .into_iter()
+            .map(|item| Entry {
+                coor: to_f64(&item.get_coordinates()),
+                time: item.get_time(),
+                item,
+            })
+            .collect();
+        Self::split(&mut entries, 0);
+        KdTree { entries, phantom: PhantomData }
+    }
+    fn split(entries: &mut [Entry<S>], depth: usize) {
+        if entries.len() <= 1 {
+            return;
+        }
+        let axis = depth % 3;
+        let mid = entries.len() / 2;
+        entries.select_nth_unstable_by(mid, |a, b| a.coor[axis].total_cmp(&b.coor[axis]));
+        let (left, right) = entries.split_at_mut(mid);
+        Self::split(left, depth + 1);
+        Self::split(&mut right[1..], depth + 1);
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /*-- indexed objects, in tree order --*/
+    pub fn iter(&self) -> impl Iterator<Item = &S> {
+        self.entries.iter().map(|e| &e.item)
+    }
+    pub fn into_items(self) -> Vec<S> {
+        self.entries.into_iter().map(|e| e.item).collect()
+    }
+    fn accept(e: &Entry<S>, window: Option<&TimeWindow>) -> bool {
+        window.map_or(true, |w| w.contains(e.time))
+    }
+    /*-- closest object to q --*/
+    pub fn nearest(&self, q: &[T; 3], window: Option<&TimeWindow>) -> Option<&S> {
+        self.k_nearest(q, 1, window).into_iter().next()
+    }
+    /*-- up to k closest objects to q, closest first --*/
+    pub fn k_nearest(&self, q: &[T; 3], k: usize, window: Option<&TimeWindow>) -> Vec<&S> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let q = to_f64(q);
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        self.knn(0, self.entries.len(), 0, &q, k, window, &mut heap);
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|c| &self.entries[c.idx].item)
+            .collect()
+    }
+    #[allow(clippy::too_many_arguments)]
+    fn knn(
+        &self, lo: usize, hi: usize, depth: usize, q: &[f64; 3], k: usize,
+        window: Option<&TimeWindow>, heap: &mut BinaryHeap<Candidate>
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let e = &self.entries[mid];
+        if Self::accept(e, window) {
+            heap.push(Candidate { d2: dist_sq(&e.coor, q), idx: mid });
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        let axis = depth % 3;
+        let diff = q[axis] - e.coor[axis];
+        let (near, far) = if diff < 0.0 {
+            ((lo, mid), (mid + 1, hi))
+        } else {
+            ((mid + 1, hi), (lo, mid))
+        };
+        self.knn(near.0, near.1, depth + 1, q, k, window, heap);
+        let worst = heap.peek().map_or(f64::INFINITY, |c| c.d2);
+        if heap.len() < k || diff * diff <= worst {
+            self.knn(far.0, far.1, depth + 1, q, k, window, heap);
+        }
+    }
+    /*-- all objects no farther than r from q --*/
+    pub fn within_radius(&self, q: &[T; 3], r: T, window: Option<&TimeWindow>) -> Vec<&S> {
+        let q = to_f64(q);
+        let r = r.to_f64().unwrap_or(f64::NAN);
+        let (lo, hi) = ([q[0] - r, q[1] - r, q[2] - r], [q[0] + r, q[1] + r, q[2] + r]);
+        let mut found = Vec::new();
+        self.boxed(0, self.entries.len(), 0, &lo, &hi, &mut |e: &Entry<S>| {
+            if dist_sq(&e.coor, &q) <= r * r && Self::accept(e, window) {
+                found.push(&e.item);
+            }
+        });
+        found
+    }
+    /*-- all objects with min <= coordinate <= max on every axis --*/
+    pub fn within_box(&self, min: &[T; 3], max: &[T; 3], window: Option<&TimeWindow>) -> Vec<&S> {
+        let (lo, hi) = (to_f64(min), to_f64(max));
+        let mut found = Vec::new();
+        self.boxed(0, self.entries.len(), 0, &lo, &hi, &mut |e: &Entry<S>| {
+            if Self::accept(e, window) {
+                found.push(&e.item);
+            }
+        });
+        found
+    }
+    /*-- visit every entry inside [lo, hi] --*/
+    fn boxed<'a, F>(
+        &'a self, lo: usize, hi: usize, depth: usize,
+        bmin: &[f64; 3], bmax: &[f64; 3], visit: &mut F
+    )
+    where F: FnMut(&'a Entry<S>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let e = &self.entries[mid];
+        if (0..3).all(|k| bmin[k] <= e.coor[k] && e.coor[k] <= bmax[k]) {
+            visit(e);
+        }
+        let axis = depth % 3;
+        if bmin[axis] <= e.coor[axis] {
+            self.boxed(lo, mid, depth + 1, bmin, bmax, visit);
+        }
+        if e.coor[axis] <= bmax[axis] {
+            self.boxed(mid + 1, hi, depth + 1, bmin, bmax, visit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+    use chrono::Duration;
+    fn grid(t0: DateTime<Utc>) -> Vec<Point<f64>> {
+        let mut v = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                for k in 0..10 {
+                    let mut p = Point::<f64>::new();
+                    p.set_coordinates(&[i as f64, j as f64, k as f64]);
+                    p.set_time(t0 + Duration::seconds(i));
+                    v.push(p);
+                }
+            }
+        }
+        v
+    }
+    /*-- brute force reference for k-nearest --*/
+    fn linear_knn(pts: &[Point<f64>], q: &[f64; 3], k: usize) -> Vec<f64> {
+        let mut d: Vec<f64> = pts.iter().map(|p| dist_sq(&p.get_coordinates(), q)).collect();
+        d.sort_by(|a, b| a.total_cmp(b));
+        d.truncate(k);
+        d
+    }
+    #[test]
+    fn nearest_and_k_nearest() {
+        let t0 = Utc::now();
+        let pts = grid(t0);
+        let tree = KdTree::build(pts.clone());
+        assert_eq!(tree.len(), 1000);
+        let q = [3.2, 4.9, 7.6];
+        let n = tree.nearest(&q, None).unwrap();
+        assert_eq!(n.get_coordinates(), [3.0, 5.0, 8.0]);
+        let k: Vec<f64> = tree.k_nearest(&q, 7, None)
+            .iter().map(|p| dist_sq(&p.get_coordinates(), &q)).collect();
+        assert_eq!(k, linear_knn(&pts, &q, 7));
+    }
+    #[test]
+    fn radius_and_box() {
+        let t0 = Utc::now();
+        let tree = KdTree::build(grid(t0));
+        let r = tree.within_radius(&[5.0, 5.0, 5.0], 1.0, None);
+        assert_eq!(r.len(), 7);
+        let b = tree.within_box(&[0.0, 0.0, 0.0], &[1.0, 2.0, 3.0], None);
+        assert_eq!(b.len(), 2 * 3 * 4);
+    }
+    #[test]
+    fn time_window_filters() {
+        let t0 = Utc::now();
+        let tree = KdTree::build(grid(t0));
+        let w = TimeWindow::new(t0 + Duration::seconds(7), t0 + Duration::seconds(8));
+        let n = tree.nearest(&[0.0, 0.0, 0.0], Some(&w)).unwrap();
+        assert_eq!(n.get_coordinates(), [7.0, 0.0, 0.0]);
+        let b = tree.within_box(&[0.0, 0.0, 0.0], &[9.0, 0.0, 0.0], Some(&w));
+        assert_eq!(b.len(), 2);
+    }
+}
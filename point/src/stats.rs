@@ -0,0 +1,286 @@
+/////////////////////////////////////////////////////////////
+// point::stats.rs - Bounds and statistics of collections  //
+/////////////////////////////////////////////////////////////
+/*
+   Aggregates over slices of Point<T>, or of any SpaceTime<T>
+   implementor:
+   - bounding_box:   Aabb<T>, the axis-aligned bounding box
+   - centroid:       mean position
+   - covariance:     3x3 population covariance, divides by n
+   - principal_axes: eigenvalues and unit eigenvectors of the
+                     covariance, largest spread first
+   - time_span:      TimeWindow from earliest to latest time
+   summarize computes all of them in two passes.
+   Statistics are computed in f64, so integer coordinates
+   work too.  Every function returns None for an empty slice.
+*/
+use super::spatial::to_f64;
+use super::{SpaceTime, TimeWindow};
+use num_traits::{Num, ToPrimitive};
+
+/*-- define Aabb<T> type, closed box min <= c <= max --*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb<T> {
+    pub min: [T; 3],
+    pub max: [T; 3],
+}
+impl<T: Copy + PartialOrd> Aabb<T> {
+    /*-- box holding just c --*/
+    pub fn from_coordinates(c: &[T; 3]) -> Aabb<T> {
+        Aabb { min: *c, max: *c }
+    }
+    /*-- grow to hold c --*/
+    pub fn extend(&mut self, c: &[T; 3]) {
+        for (k, v) in c.iter().enumerate() {
+            if *v < self.min[k] {
+                self.min[k] = *v;
+            }
+            if *v > self.max[k] {
+                self.max[k] = *v;
+            }
+        }
+    }
+    pub fn union(&self, other: &Aabb<T>) -> Aabb<T> {
+        let mut u = *self;
+        u.extend(&other.min);
+        u.extend(&other.max);
+        u
+    }
+    pub fn contains(&self, c: &[T; 3]) -> bool {
+        (0..3).all(|k| self.min[k] <= c[k] && c[k] <= self.max[k])
+    }
+    pub fn intersects(&self, other: &Aabb<T>) -> bool {
+        (0..3).all(|k| self.min[k] <= other.max[k] && other.min[k] <= self.max[k])
+    }
+}
+impl<T: Copy + PartialOrd + Num> Aabb<T> {
+    /*-- edge lengths --*/
+    pub fn size(&self) -> [T; 3] {
+        [self.max[0] - self.min[0], self.max[1] - self.min[1], self.max[2] - self.min[2]]
+    }
+    pub fn center(&self) -> [T; 3] {
+        let two = T::one() + T::one();
+        [
+            (self.min[0] + self.max[0]) / two,
+            (self.min[1] + self.max[1]) / two,
+            (self.min[2] + self.max[2]) / two,
+        ]
+    }
+    pub fn volume(&self) -> T {
+        let s = self.size();
+        s[0] * s[1] * s[2]
+    }
+}
+
+/*-- principal directions of a point cloud --*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrincipalAxes {
+    /*-- variances along each axis, largest first --*/
+    pub variances: [f64; 3],
+    /*-- unit vectors, axes[i] goes with variances[i] --*/
+    pub axes: [[f64; 3]; 3],
+}
+
+/*-- all aggregates of one collection --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary<T> {
+    pub count: usize,
+    pub bounds: Aabb<T>,
+    pub centroid: [f64; 3],
+    pub covariance: [[f64; 3]; 3],
+    pub time_span: TimeWindow,
+}
+impl<T> Summary<T> {
+    pub fn principal_axes(&self) -> PrincipalAxes {
+        eigen_symmetric(&self.covariance)
+    }
+}
+
+pub fn bounding_box<T, S>(pts: &[S]) -> Option<Aabb<T>>
+where T: Copy + PartialOrd, S: SpaceTime<T> {
+    let (first, rest) = pts.split_first()?;
+    let mut b = Aabb::from_coordinates(&first.get_coordinates());
+    for p in rest {
+        b.extend(&p.get_coordinates());
+    }
+    Some(b)
+}
+pub fn centroid<T, S>(pts: &[S]) -> Option<[f64; 3]>
+where T: ToPrimitive, S: SpaceTime<T> {
+    if pts.is_empty() {
+        return None;
+    }
+    let mut sum = [0.0; 3];
+    for p in pts {
+        let c = to_f64(&p.get_coordinates());
+        for k in 0..3 {
+            sum[k] += c[k];
+        }
+    }
+    let n = pts.len() as f64;
+    Some([sum[0] / n, sum[1] / n, sum[2] / n])
+}
+/*-- second pass about the centroid, avoids cancellation --*/
+pub fn covariance<T, S>(pts: &[S]) -> Option<[[f64; 3]; 3]>
+where T: ToPrimitive, S: SpaceTime<T> {
+    let m = centroid(pts)?;
+    Some(covariance_about(pts, &m))
+}
+fn covariance_about<T, S>(pts: &[S], m: &[f64; 3]) -> [[f64; 3]; 3]
+where T: ToPrimitive, S: SpaceTime<T> {
+    let mut cov = [[0.0; 3]; 3];
+    for p in pts {
+        let c = to_f64(&p.get_coordinates());
+        let d = [c[0] - m[0], c[1] - m[1], c[2] - m[2]];
+        for (i, row) in cov.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v += d[i] * d[j];
+            }
+        }
+    }
+    let n = pts.len() as f64;
+    for row in cov.iter_mut() {
+        for v in row.iter_mut() {
+            *v /= n;
+        }
+    }
+    cov
+}
+pub fn principal_axes<T, S>(pts: &[S]) -> Option<PrincipalAxes>
+where T: ToPrimitive, S: SpaceTime<T> {
+    covariance(pts).map(|c| eigen_symmetric(&c))
+}
+pub fn time_span<T, S>(pts: &[S]) -> Option<TimeWindow>
+where S: SpaceTime<T> {
+    let start = pts.iter().map(|p| p.get_time()).min()?;
+    let end = pts.iter().map(|p| p.get_time()).max()?;
+    Some(TimeWindow::new(start, end))
+}
+/*-- bounds, sum, and times in one pass, covariance in a second --*/
+pub fn summarize<T, S>(pts: &[S]) -> Option<Summary<T>>
+where T: Copy + PartialOrd + ToPrimitive, S: SpaceTime<T> {
+    let (first, rest) = pts.split_first()?;
+    let mut bounds = Aabb::from_coordinates(&first.get_coordinates());
+    let mut sum = to_f64(&first.get_coordinates());
+    let (mut start, mut end) = (first.get_time(), first.get_time());
+    for p in rest {
+        let c = p.get_coordinates();
+        bounds.extend(&c);
+        for (s, v) in sum.iter_mut().zip(to_f64(&c).iter()) {
+            *s += v;
+        }
+        let t = p.get_time();
+        start = start.min(t);
+        end = end.max(t);
+    }
+    let n = pts.len() as f64;
+    let centroid = [sum[0] / n, sum[1] / n, sum[2] / n];
+    Some(Summary {
+        count: pts.len(),
+        bounds,
+        centroid,
+        covariance: covariance_about(pts, &centroid),
+        time_span: TimeWindow::new(start, end),
+    })
+}
+
+/*-----------------------------------------------------------
+  eigen decomposition of a symmetric 3x3 matrix by cyclic
+  Jacobi rotations, sorted by decreasing eigenvalue
+*/
+fn eigen_symmetric(m: &[[f64; 3]; 3]) -> PrincipalAxes {
+    let mut a = *m;
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..50 {
+        let off = a[0][1].abs() + a[0][2].abs() + a[1][2].abs();
+        if off < 1e-15 * (a[0][0].abs() + a[1][1].abs() + a[2][2].abs()).max(f64::MIN_POSITIVE) {
+            break;
+        }
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q] == 0.0 {
+                continue;
+            }
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let t = if theta == 0.0 { 1.0 } else { t };
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+            /*-- a = J' a J, v = v J, with J the (p, q) rotation --*/
+            for row in a.iter_mut() {
+                let (akp, akq) = (row[p], row[q]);
+                row[p] = c * akp - s * akq;
+                row[q] = s * akp + c * akq;
+            }
+            let (rp, rq) = (a[p], a[q]);
+            for k in 0..3 {
+                a[p][k] = c * rp[k] - s * rq[k];
+                a[q][k] = s * rp[k] + c * rq[k];
+            }
+            for row in v.iter_mut() {
+                let (vp, vq) = (row[p], row[q]);
+                row[p] = c * vp - s * vq;
+                row[q] = s * vp + c * vq;
+            }
+        }
+    }
+    let mut order = [0, 1, 2];
+    order.sort_by(|&i, &j| a[j][j].partial_cmp(&a[i][i]).unwrap_or(std::cmp::Ordering::Equal));
+    let col = |j: usize| [v[0][j], v[1][j], v[2][j]];
+    PrincipalAxes {
+        variances: [a[order[0]][order[0]], a[order[1]][order[1]], a[order[2]][order[2]]],
+        axes: [col(order[0]), col(order[1]), col(order[2])],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+    use chrono::{Duration, Utc};
+    fn pts<T: Default + std::fmt::Debug + Clone>(c: &[[T; 3]]) -> Vec<Point<T>> {
+        let t0 = Utc::now();
+        c.iter().enumerate().map(|(i, c)| {
+            let mut p = Point::new();
+            p.set_coordinates(c);
+            p.set_time(t0 - Duration::seconds(i as i64));
+            p
+        }).collect()
+    }
+    #[test]
+    fn boxes_and_centroid() {
+        let p = pts(&[[1, 5, -2], [3, -1, 0], [2, 2, 8]]);
+        let b = bounding_box(&p).unwrap();
+        assert_eq!(b, Aabb { min: [1, -1, -2], max: [3, 5, 8] });
+        assert_eq!(b.size(), [2, 6, 10]);
+        assert_eq!(b.volume(), 120);
+        assert!(b.contains(&[2, 0, 0]) && !b.contains(&[0, 0, 0]));
+        let other = Aabb { min: [3, 5, 8], max: [4, 6, 9] };
+        assert!(b.intersects(&other));
+        assert_eq!(b.union(&other).max, [4, 6, 9]);
+        assert_eq!(centroid(&p).unwrap(), [2.0, 2.0, 2.0]);
+        let span = time_span(&p).unwrap();
+        assert_eq!(span.end - span.start, Duration::seconds(2));
+        assert!(bounding_box::<i32, Point<i32>>(&[]).is_none());
+    }
+    #[test]
+    fn covariance_and_axes() {
+        /*-- points along the direction (1, 1, 0) with a little spread in z --*/
+        let p = pts(&[[-2.0, -2.0, 0.1], [-1.0, -1.0, -0.1], [1.0, 1.0, -0.1], [2.0, 2.0, 0.1]]);
+        let c = covariance(&p).unwrap();
+        assert!((c[0][0] - 2.5).abs() < 1e-12 && (c[0][1] - 2.5).abs() < 1e-12);
+        assert!((c[2][2] - 0.01).abs() < 1e-12);
+        let pa = principal_axes(&p).unwrap();
+        assert!((pa.variances[0] - 5.0).abs() < 1e-9);
+        assert!((pa.variances[1] - 0.01).abs() < 1e-9);
+        assert!(pa.variances[2].abs() < 1e-9);
+        let h = 0.5f64.sqrt();
+        assert!((pa.axes[0][0].abs() - h).abs() < 1e-9 && (pa.axes[0][1].abs() - h).abs() < 1e-9);
+        assert!((pa.axes[1][2].abs() - 1.0).abs() < 1e-9);
+        let s = summarize(&p).unwrap();
+        assert_eq!(s.count, 4);
+        assert_eq!(s.covariance, c);
+        assert_eq!(s.principal_axes(), pa);
+        assert_eq!((s.centroid, s.bounds), (centroid(&p).unwrap(), bounding_box(&p).unwrap()));
+        assert_eq!(s.time_span, time_span(&p).unwrap());
+    }
+}
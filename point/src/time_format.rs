@@ -0,0 +1,148 @@
+/////////////////////////////////////////////////////////////
+// point::time_format.rs - Text forms of SpaceTime times   //
+/////////////////////////////////////////////////////////////
+/*
+   TimeFormat selects how SpaceTime::format_time writes a
+   time.  All forms are written in UTC, so text does not
+   depend on the time zone of the machine that wrote it.
+   - Rfc3339:      2020-06-24T13:05:09.250Z
+   - Iso8601Basic: 20200624T130509.250Z
+   - UnixSeconds:  1593003909.250
+   Precision picks the number of sub-second digits.  Auto
+   writes 0, 3, 6, or 9 digits, as few as the value needs.
+   parse_time reads any of these layouts back.  RFC-3339 text
+   may carry any UTC offset.
+*/
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, TimeZone, Timelike, Utc};
+
+/*-- number of sub-second digits --*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+    #[default]
+    Auto,
+}
+impl Precision {
+    fn digits(self, t: &DateTime<Utc>) -> usize {
+        let ns = t.nanosecond() % 1_000_000_000;
+        match self {
+            Precision::Seconds => 0,
+            Precision::Millis => 3,
+            Precision::Micros => 6,
+            Precision::Nanos => 9,
+            Precision::Auto if ns == 0 => 0,
+            Precision::Auto if ns % 1_000_000 == 0 => 3,
+            Precision::Auto if ns % 1_000 == 0 => 6,
+            Precision::Auto => 9,
+        }
+    }
+}
+
+/*-- layout of time text --*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeFormat {
+    Rfc3339(Precision),
+    Iso8601Basic(Precision),
+    UnixSeconds(Precision),
+}
+impl Default for TimeFormat {
+    fn default() -> TimeFormat {
+        TimeFormat::Rfc3339(Precision::Auto)
+    }
+}
+impl TimeFormat {
+    pub fn format(&self, t: &DateTime<Utc>) -> String {
+        match *self {
+            TimeFormat::Rfc3339(p) => {
+                let sf = match p.digits(t) {
+                    0 => SecondsFormat::Secs,
+                    3 => SecondsFormat::Millis,
+                    6 => SecondsFormat::Micros,
+                    _ => SecondsFormat::Nanos,
+                };
+                t.to_rfc3339_opts(sf, true)
+            }
+            TimeFormat::Iso8601Basic(p) => {
+                let mut s = t.format("%Y%m%dT%H%M%S").to_string();
+                s.push_str(&fraction(t, p.digits(t)));
+                s.push('Z');
+                s
+            }
+            TimeFormat::UnixSeconds(p) => {
+                format!("{}{}", t.timestamp(), fraction(t, p.digits(t)))
+            }
+        }
+    }
+}
+/*-- ".ddd" with the requested number of digits, empty for none --*/
+fn fraction(t: &DateTime<Utc>, digits: usize) -> String {
+    if digits == 0 {
+        return String::new();
+    }
+    let ns = format!("{:09}", t.nanosecond() % 1_000_000_000);
+    format!(".{}", &ns[..digits])
+}
+
+/*-- read time written in any TimeFormat layout --*/
+pub fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Some(t.with_timezone(&Utc));
+    }
+    if let Some(basic) = s.strip_suffix('Z') {
+        let t = NaiveDateTime::parse_from_str(basic, "%Y%m%dT%H%M%S%.f").ok()?;
+        return Some(Utc.from_utc_datetime(&t));
+    }
+    /*-- Unix seconds, with up to nine fraction digits --*/
+    let (whole, frac) = match s.find('.') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, ""),
+    };
+    if frac.len() > 9 || !frac.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let secs: i64 = whole.parse().ok()?;
+    let mut nanos: u32 = format!("{:0<9}", frac).parse().ok()?;
+    let secs = if whole.starts_with('-') && nanos > 0 {
+        /*-- -1.25 is 1.25 s before the epoch: -2 s + 0.75 s --*/
+        nanos = 1_000_000_000 - nanos;
+        secs - 1
+    } else {
+        secs
+    };
+    Utc.timestamp_opt(secs, nanos).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    #[test]
+    fn layouts() {
+        let t = Utc.timestamp_opt(1_593_003_909, 250_000_000).unwrap();
+        assert_eq!(TimeFormat::default().format(&t), "2020-06-24T13:05:09.250Z");
+        assert_eq!(TimeFormat::Rfc3339(Precision::Seconds).format(&t), "2020-06-24T13:05:09Z");
+        assert_eq!(TimeFormat::Rfc3339(Precision::Nanos).format(&t), "2020-06-24T13:05:09.250000000Z");
+        assert_eq!(TimeFormat::Iso8601Basic(Precision::Millis).format(&t), "20200624T130509.250Z");
+        assert_eq!(TimeFormat::UnixSeconds(Precision::Auto).format(&t), "1593003909.250");
+        let whole = Utc.timestamp_opt(1_593_003_909, 0).unwrap();
+        assert_eq!(TimeFormat::default().format(&whole), "2020-06-24T13:05:09Z");
+    }
+    #[test]
+    fn parse_layouts() {
+        let t = Utc.timestamp_opt(1_593_003_909, 250_000_000).unwrap();
+        for fmt in &[
+            TimeFormat::Rfc3339(Precision::Auto),
+            TimeFormat::Iso8601Basic(Precision::Nanos),
+            TimeFormat::UnixSeconds(Precision::Millis),
+        ] {
+            assert_eq!(parse_time(&fmt.format(&t)), Some(t));
+        }
+        assert_eq!(parse_time("2020-06-24T18:05:09.25+05:00"), Some(t));
+        assert_eq!(parse_time("-1.25"), Utc.timestamp_opt(-2, 750_000_000).single());
+        assert_eq!(parse_time("not a time"), None);
+    }
+}
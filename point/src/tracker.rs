@@ -0,0 +1,414 @@
+/////////////////////////////////////////////////////////////
+// point::tracker.rs - Measurement-to-track association    //
+/////////////////////////////////////////////////////////////
+/*
+   Tracker turns scans of anonymous Point<f64> measurements
+   into persistent, named tracks.
+   - Each track runs a constant-velocity KalmanFilter and is
+     predicted to the time of each measurement.
+   - Gating: a measurement farther than the gate distance from
+     a track's predicted position can't be assigned to it.
+     An infinite gate turns gating off.
+   - Association::NearestNeighbor assigns the closest gated
+     pair first, then the next closest, and so on.
+   - Association::GlobalNearestNeighbor finds the assignment
+     with least total distance, counting the gate distance for
+     each track left unassigned (Hungarian algorithm).
+   Each assigned measurement is renamed with set_name to its
+   track's name.  Measurements left over start Tentative
+   tracks.  A track is Confirmed after confirm_hits updates,
+   and is retired after more than max_misses scans in a row
+   without a measurement.  A Tentative track is retired at its
+   first miss.
+*/
+use super::{Estimate, KalmanFilter, Point, SpaceTime};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Association {
+    NearestNeighbor,
+    GlobalNearestNeighbor,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackState {
+    Tentative,
+    Confirmed,
+}
+
+/*-- tracker settings, see module comment --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackerConfig {
+    pub association: Association,
+    pub gate: f64,
+    pub confirm_hits: u32,
+    pub max_misses: u32,
+    pub process_noise: f64,
+    pub measurement_noise: f64,
+    pub name_prefix: String,
+}
+impl Default for TrackerConfig {
+    fn default() -> TrackerConfig {
+        TrackerConfig {
+            association: Association::GlobalNearestNeighbor,
+            gate: 10.0,
+            confirm_hits: 3,
+            max_misses: 2,
+            process_noise: 1.0,
+            measurement_noise: 1.0,
+            name_prefix: "track-".to_string(),
+        }
+    }
+}
+
+/*-- define Track type --*/
+#[derive(Debug, Clone)]
+pub struct Track {
+    name: String,
+    state: TrackState,
+    filter: KalmanFilter,
+    hits: u32,
+    misses: u32,
+}
+impl Track {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn state(&self) -> TrackState {
+        self.state
+    }
+    pub fn hits(&self) -> u32 {
+        self.hits
+    }
+    /*-- scans in a row without a measurement --*/
+    pub fn misses(&self) -> u32 {
+        self.misses
+    }
+    pub fn estimate(&self) -> Option<Estimate> {
+        self.filter.estimate()
+    }
+    pub fn last_update(&self) -> Option<DateTime<Utc>> {
+        self.filter.estimate().map(|e| e.time)
+    }
+    /*-- predicted position at t --*/
+    pub fn predict(&self, t: DateTime<Utc>) -> Option<[f64; 3]> {
+        self.filter.predict(t).map(|e| e.position)
+    }
+}
+
+/*-- what one scan did --*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanReport {
+    /*-- (measurement index, track name) --*/
+    pub assigned: Vec<(usize, String)>,
+    pub started: Vec<String>,
+    pub confirmed: Vec<String>,
+    pub retired: Vec<String>,
+}
+
+/*-- define Tracker type --*/
+#[derive(Debug, Clone)]
+pub struct Tracker {
+    config: TrackerConfig,
+    tracks: Vec<Track>,
+    next_id: u64,
+}
+impl Tracker {
+    /*-- gate must not be NaN or negative, infinity turns gating off --*/
+    pub fn new(config: TrackerConfig) -> Tracker {
+        assert!(config.gate >= 0.0, "tracker gate must be non-negative, not NaN");
+        Tracker { config, tracks: Vec::new(), next_id: 1 }
+    }
+    pub fn config(&self) -> &TrackerConfig {
+        &self.config
+    }
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+    pub fn track(&self, name: &str) -> Option<&Track> {
+        self.tracks.iter().find(|t| t.name == name)
+    }
+    /*-----------------------------------------------------------
+      associate one scan of measurements, update, start, and
+      retire tracks, and name the assigned measurements
+    */
+    pub fn process_scan(&mut self, scan: &mut [Point<f64>]) -> ScanReport {
+        let mut report = ScanReport::default();
+        let gate = self.config.gate;
+        let cost: Vec<Vec<f64>> = self.tracks.iter().map(|tr| {
+            scan.iter().map(|m| {
+                let p = tr.predict(m.get_time()).unwrap_or([f64::INFINITY; 3]);
+                let c = m.get_coordinates();
+                ((p[0] - c[0]).powi(2) + (p[1] - c[1]).powi(2) + (p[2] - c[2]).powi(2)).sqrt()
+            }).collect()
+        }).collect();
+        let mut pairs = match self.config.association {
+            Association::NearestNeighbor => greedy(&cost, gate),
+            Association::GlobalNearestNeighbor => optimal(&cost, gate),
+        };
+        let mut track_used = vec![false; self.tracks.len()];
+        let mut meas_used = vec![false; scan.len()];
+        /*-- update in measurement time order, so filters see times in order --*/
+        pairs.sort_by_key(|&(_, j)| scan[j].get_time());
+        for (i, j) in pairs {
+            let tr = &mut self.tracks[i];
+            if tr.filter.update(&scan[j]).is_err() {
+                continue;
+            }
+            track_used[i] = true;
+            meas_used[j] = true;
+            tr.hits += 1;
+            tr.misses = 0;
+            scan[j].set_name(&tr.name);
+            report.assigned.push((j, tr.name.clone()));
+            if tr.state == TrackState::Tentative && tr.hits >= self.config.confirm_hits {
+                tr.state = TrackState::Confirmed;
+                report.confirmed.push(tr.name.clone());
+            }
+        }
+        /*-- misses and retirement --*/
+        let max_misses = self.config.max_misses;
+        for (tr, used) in self.tracks.iter_mut().zip(track_used) {
+            if !used {
+                tr.misses += 1;
+            }
+        }
+        self.tracks.retain(|tr| {
+            let keep = match tr.state {
+                TrackState::Tentative => tr.misses == 0,
+                TrackState::Confirmed => tr.misses <= max_misses,
+            };
+            if !keep {
+                report.retired.push(tr.name.clone());
+            }
+            keep
+        });
+        /*-- new tracks from leftover measurements --*/
+        for (j, m) in scan.iter_mut().enumerate() {
+            if meas_used[j] {
+                continue;
+            }
+            let mut filter = KalmanFilter::constant_velocity(self.config.process_noise, self.config.measurement_noise);
+            if filter.update(&*m).is_err() {
+                continue;
+            }
+            let name = format!("{}{}", self.config.name_prefix, self.next_id);
+            self.next_id += 1;
+            m.set_name(&name);
+            let state = if self.config.confirm_hits <= 1 { TrackState::Confirmed } else { TrackState::Tentative };
+            self.tracks.push(Track { name: name.clone(), state, filter, hits: 1, misses: 0 });
+            report.assigned.push((j, name.clone()));
+            report.started.push(name.clone());
+            if state == TrackState::Confirmed {
+                report.confirmed.push(name);
+            }
+        }
+        report.assigned.sort();
+        report
+    }
+}
+
+/*-- finite and inside the gate --*/
+fn gated(c: f64, gate: f64) -> bool {
+    c.is_finite() && c <= gate
+}
+/*-- closest gated pairs first, each track and measurement used once --*/
+fn greedy(cost: &[Vec<f64>], gate: f64) -> Vec<(usize, usize)> {
+    let mut cand: Vec<(f64, usize, usize)> = Vec::new();
+    for (i, row) in cost.iter().enumerate() {
+        for (j, &c) in row.iter().enumerate() {
+            if gated(c, gate) {
+                cand.push((c, i, j));
+            }
+        }
+    }
+    cand.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let rows = cost.len();
+    let cols = cost.first().map_or(0, |r| r.len());
+    let (mut ru, mut cu) = (vec![false; rows], vec![false; cols]);
+    let mut out = Vec::new();
+    for (_, i, j) in cand {
+        if !ru[i] && !cu[j] {
+            ru[i] = true;
+            cu[j] = true;
+            out.push((i, j));
+        }
+    }
+    out
+}
+/*-----------------------------------------------------------
+  least total cost assignment, Hungarian algorithm with
+  potentials, O(n^3)
+  - pairs beyond the gate cost the gate, the same as leaving
+    the track unassigned, and are dropped from the result
+  - with an infinite gate, leaving a track unassigned costs
+    more than any assignment, kept finite so the potentials
+    stay finite
+*/
+fn optimal(cost: &[Vec<f64>], gate: f64) -> Vec<(usize, usize)> {
+    let n = cost.len();
+    let nm = cost.first().map_or(0, |r| r.len());
+    if n == 0 || nm == 0 {
+        return Vec::new();
+    }
+    /*-- rows are tracks, columns are measurements plus dummies --*/
+    let m = n.max(nm);
+    let miss = if gate.is_finite() {
+        gate
+    } else {
+        cost.iter().flatten().filter(|c| c.is_finite()).fold(1.0, |s, c| s + c).min(1e300)
+    };
+    let a = |i: usize, j: usize| if j < nm && gated(cost[i][j], gate) { cost[i][j] } else { miss };
+    let inf = f64::INFINITY;
+    let (mut u, mut v) = (vec![0.0; n + 1], vec![0.0; m + 1]);
+    let (mut p, mut way) = (vec![0usize; m + 1], vec![0usize; m + 1]);
+    for i in 1..=n {
+        p[0] = i;
+        let mut j0 = 0;
+        let mut minv = vec![inf; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[j0] = true;
+            let i0 = p[j0];
+            let (mut delta, mut j1) = (inf, 0);
+            for j in 1..=m {
+                if !used[j] {
+                    let cur = a(i0 - 1, j - 1) - u[i0] - v[j];
+                    if cur < minv[j] {
+                        minv[j] = cur;
+                        way[j] = j0;
+                    }
+                    if minv[j] < delta {
+                        delta = minv[j];
+                        j1 = j;
+                    }
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    u[p[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minv[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if p[j0] == 0 {
+                break;
+            }
+        }
+        loop {
+            let j1 = way[j0];
+            p[j0] = p[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+    (1..=nm)
+        .filter(|&j| p[j] != 0 && gated(cost[p[j] - 1][j - 1], gate))
+        .map(|j| (p[j] - 1, j - 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    fn scan(secs: i64, cs: &[[f64; 3]]) -> Vec<Point<f64>> {
+        cs.iter().map(|c| {
+            let mut p = Point::new();
+            p.set_coordinates(c);
+            p.set_time(Utc.timestamp_opt(1_000 + secs, 0).unwrap());
+            p
+        }).collect()
+    }
+    fn config(association: Association) -> TrackerConfig {
+        TrackerConfig { association, gate: 8.0, process_noise: 0.01, measurement_noise: 0.1, ..TrackerConfig::default() }
+    }
+    #[test]
+    fn tracks_two_targets() {
+        let mut tk = Tracker::new(config(Association::GlobalNearestNeighbor));
+        let mut names = Vec::new();
+        for k in 0..10 {
+            let x = k as f64 * 5.0;
+            /*-- report order alternates, tracks must not swap, a bad report takes no name --*/
+            let mut s = if k == 0 {
+                scan(k, &[[f64::NAN, f64::NAN, 0.0], [x, 0.0, 0.0], [100.0 - x, 30.0, 0.0]])
+            } else if k % 2 == 0 {
+                scan(k, &[[x, 0.0, 0.0], [100.0 - x, 30.0, 0.0]])
+            } else {
+                scan(k, &[[100.0 - x, 30.0, 0.0], [x, 0.0, 0.0]])
+            };
+            let r = tk.process_scan(&mut s);
+            if k == 0 {
+                assert_eq!(r.started, ["track-1", "track-2"]);
+            }
+            if k == 2 {
+                assert_eq!(r.confirmed, ["track-1", "track-2"]);
+            }
+            let east = s.iter().find(|p| p.get_coordinates()[1] == 0.0).unwrap();
+            names.push(east.get_name().to_string());
+        }
+        assert!(names.iter().all(|n| n == "track-1"));
+        let v = tk.track("track-1").unwrap().estimate().unwrap().velocity;
+        assert!((v[0] - 5.0).abs() < 0.1);
+        assert_eq!(tk.track("track-2").unwrap().state(), TrackState::Confirmed);
+    }
+    #[test]
+    fn global_beats_greedy() {
+        /*-- stationary tracks at x = 0 and x = 10 --*/
+        let run = |association| {
+            let mut tk = Tracker::new(TrackerConfig { confirm_hits: 1, ..config(association) });
+            for k in 0..3 {
+                tk.process_scan(&mut scan(k, &[[0.0, 0.0, 0.0], [10.0, 0.0, 0.0]]));
+            }
+            let mut s = scan(3, &[[6.0, 0.0, 0.0], [15.0, 0.0, 0.0]]);
+            let r = tk.process_scan(&mut s);
+            (r, s)
+        };
+        /*-- greedy takes 6 -> track-2 (4 away), leaving 15 unmatched --*/
+        let (r, s) = run(Association::NearestNeighbor);
+        assert_eq!(s[0].get_name(), "track-2");
+        assert_eq!(r.started, ["track-3"]);
+        /*-- global: 6 -> track-1, 15 -> track-2, total 11 < 4 + gate --*/
+        let (r, s) = run(Association::GlobalNearestNeighbor);
+        assert_eq!((s[0].get_name(), s[1].get_name()), ("track-1", "track-2"));
+        assert!(r.started.is_empty());
+    }
+    #[test]
+    fn infinite_gate() {
+        for association in [Association::NearestNeighbor, Association::GlobalNearestNeighbor] {
+            let mut tk = Tracker::new(TrackerConfig { gate: f64::INFINITY, ..config(association) });
+            tk.process_scan(&mut scan(0, &[[0.0; 3], [1_000.0, 0.0, 0.0]]));
+            let mut s = scan(1, &[[900.0, 0.0, 0.0]]);
+            let r = tk.process_scan(&mut s);
+            assert_eq!(s[0].get_name(), "track-2");
+            assert!(r.started.is_empty());
+            /*-- an unmatched track still costs more than a far match --*/
+            let mut s = scan(2, &[[1e9, 0.0, 0.0]]);
+            assert!(tk.process_scan(&mut s).started.is_empty());
+        }
+        for gate in [f64::NAN, -1.0] {
+            let bad = TrackerConfig { gate, ..TrackerConfig::default() };
+            assert!(std::panic::catch_unwind(|| Tracker::new(bad)).is_err());
+        }
+    }
+    #[test]
+    fn tracks_retire() {
+        let mut tk = Tracker::new(config(Association::GlobalNearestNeighbor));
+        for k in 0..3 {
+            tk.process_scan(&mut scan(k, &[[0.0; 3]]));
+        }
+        /*-- tentative clutter dies at its first miss --*/
+        tk.process_scan(&mut scan(3, &[[0.0; 3], [50.0, 0.0, 0.0]]));
+        let r = tk.process_scan(&mut scan(4, &[[0.0; 3]]));
+        assert_eq!(r.retired, ["track-2"]);
+        for k in 5..7 {
+            assert!(tk.process_scan(&mut []).retired.is_empty(), "scan {}", k);
+        }
+        assert_eq!(tk.tracks()[0].misses(), 2);
+        assert_eq!(tk.process_scan(&mut []).retired, ["track-1"]);
+        assert!(tk.tracks().is_empty());
+    }
+}
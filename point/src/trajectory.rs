@@ -0,0 +1,255 @@
+/////////////////////////////////////////////////////////////
+// point::trajectory.rs - Time-ordered Point<T> samples    //
+/////////////////////////////////////////////////////////////
+/*
+   Trajectory<T> holds Point<T> samples ordered by get_time().
+   - position_at(t, mode) answers "where was the object at time
+     t" using Linear or Cubic (Hermite) interpolation.
+   - velocity_at, speed_at, and heading_at use the straight
+     segment containing t.  Speed is in coordinate units per
+     second.  Heading is measured in degrees, clockwise from
+     the +y axis (north) toward the +x axis (east).
+   - path_length is the sum of the straight segment lengths.
+   Queries outside the recorded time span return None.
+*/
+use super::{Point, SpaceTime};
+use chrono::offset::Utc;
+use chrono::{DateTime, Duration};
+use num_traits::Float;
+use std::fmt::Debug;
+
+/*-- seconds in a chrono Duration, with sub-second precision --*/
+pub(crate) fn seconds(d: Duration) -> f64 {
+    match d.num_nanoseconds() {
+        Some(ns) => ns as f64 * 1e-9,
+        None => d.num_milliseconds() as f64 * 1e-3,
+    }
+}
+
+/*-- how to fill in positions between samples --*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    Cubic,
+}
+
+/*-- define Trajectory<T> type --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trajectory<T>
+where T: Default + Debug {
+    samples: Vec<Point<T>>,
+}
+impl<T> Default for Trajectory<T> where T: Default + Debug + Clone {
+    fn default() -> Trajectory<T> {
+        Trajectory::new()
+    }
+}
+/*-- construction and access --*/
+impl<T> Trajectory<T> where T: Default + Debug + Clone {
+    pub fn new() -> Trajectory<T> {
+        Trajectory { samples: Vec::new() }
+    }
+    /*-- samples may arrive in any order, equal times keep order --*/
+    pub fn from_points(mut pts: Vec<Point<T>>) -> Trajectory<T> {
+        pts.sort_by_key(|p| p.get_time());
+        Trajectory { samples: pts }
+    }
+    /*-- insert in time order, after any sample with equal time --*/
+    pub fn push(&mut self, pt: Point<T>) {
+        let t = pt.get_time();
+        let i = self.samples.partition_point(|p| p.get_time() <= t);
+        self.samples.insert(i, pt);
+    }
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+    pub fn samples(&self) -> &[Point<T>] {
+        &self.samples
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, Point<T>> {
+        self.samples.iter()
+    }
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        self.samples.first().map(|p| p.get_time())
+    }
+    pub fn end_time(&self) -> Option<DateTime<Utc>> {
+        self.samples.last().map(|p| p.get_time())
+    }
+    pub fn into_points(self) -> Vec<Point<T>> {
+        self.samples
+    }
+    /*-----------------------------------------------------
+      index i of segment [i, i+1] containing t
+      - t on an interior sample selects the later segment
+    */
+    fn segment(&self, t: DateTime<Utc>) -> Option<usize> {
+        let n = self.samples.len();
+        if n < 2 || t < self.samples[0].get_time() || t > self.samples[n - 1].get_time() {
+            return None;
+        }
+        let i = self.samples.partition_point(|p| p.get_time() <= t);
+        Some(i.clamp(1, n - 1) - 1)
+    }
+}
+
+/*-- interpolation and kinematics --*/
+impl<T> Trajectory<T> where T: Default + Debug + Clone + Float {
+    fn secs_from(&self, i: usize, t: DateTime<Utc>) -> T {
+        T::from(seconds(t - self.samples[i].get_time())).unwrap()
+    }
+    /*-- average velocity between samples i and j, per second --*/
+    fn velocity(&self, i: usize, j: usize) -> [T; 3] {
+        let (a, b) = (&self.samples[i], &self.samples[j]);
+        let dt = self.secs_from(i, b.get_time());
+        if dt <= T::zero() {
+            return [T::zero(); 3];
+        }
+        let d = b - a;
+        [d.x / dt, d.y / dt, d.z / dt]
+    }
+    /*-- position of the object at time t --*/
+    pub fn position_at(&self, t: DateTime<Utc>, mode: Interpolation) -> Option<Point<T>> {
+        if self.samples.len() == 1 && self.samples[0].get_time() == t {
+            return Some(self.samples[0].clone());
+        }
+        let i = self.segment(t)?;
+        let (p0, p1) = (&self.samples[i], &self.samples[i + 1]);
+        let h = self.secs_from(i, p1.get_time());
+        let mut pt = if h <= T::zero() {
+            p1.clone()
+        }
+        else {
+            let s = self.secs_from(i, t) / h;
+            match mode {
+                Interpolation::Linear => p0.lerp(p1, s),
+                Interpolation::Cubic => self.hermite(i, s, h),
+            }
+        };
+        pt.set_time(t);
+        Some(pt)
+    }
+    /*-----------------------------------------------------
+      cubic Hermite on segment i, tangents from neighbors
+      - passes through every sample
+      - s in [0, 1], h is segment duration in seconds
+    */
+    fn hermite(&self, i: usize, s: T, h: T) -> Point<T> {
+        let last = self.samples.len() - 1;
+        let m0 = self.velocity(i.saturating_sub(1), i + 1);
+        let m1 = self.velocity(i, (i + 2).min(last));
+        let one = T::one();
+        let two = one + one;
+        let three = two + one;
+        let (s2, s3) = (s * s, s * s * s);
+        let h00 = two * s3 - three * s2 + one;
+        let h10 = s3 - two * s2 + s;
+        let h01 = three * s2 - two * s3;
+        let h11 = s3 - s2;
+        let a = self.samples[i].get_coordinates();
+        let b = self.samples[i + 1].get_coordinates();
+        let mut c = [T::zero(); 3];
+        for k in 0..3 {
+            c[k] = h00 * a[k] + h10 * h * m0[k] + h01 * b[k] + h11 * h * m1[k];
+        }
+        let mut pt = self.samples[i].clone();
+        pt.set_coordinates(&c);
+        pt
+    }
+    /*-- velocity vector in units per second on segment containing t --*/
+    pub fn velocity_at(&self, t: DateTime<Utc>) -> Option<[T; 3]> {
+        let i = self.segment(t)?;
+        Some(self.velocity(i, i + 1))
+    }
+    /*-- magnitude of velocity --*/
+    pub fn speed_at(&self, t: DateTime<Utc>) -> Option<T> {
+        let v = self.velocity_at(t)?;
+        Some((v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt())
+    }
+    /*-- degrees clockwise from +y, None when not moving horizontally --*/
+    pub fn heading_at(&self, t: DateTime<Utc>) -> Option<T> {
+        let v = self.velocity_at(t)?;
+        if v[0] == T::zero() && v[1] == T::zero() {
+            return None;
+        }
+        let deg = v[0].atan2(v[1]).to_degrees();
+        let full = T::from(360.0).unwrap();
+        Some(if deg < T::zero() { deg + full } else { deg })
+    }
+    /*-- total distance along straight segments --*/
+    pub fn path_length(&self) -> T {
+        self.samples
+            .windows(2)
+            .fold(T::zero(), |acc, w| acc + w[0].distance(&w[1]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn sample(t0: DateTime<Utc>, secs: i64, c: [f64; 3]) -> Point<f64> {
+        let mut p = Point::<f64>::new();
+        p.set_coordinates(&c);
+        p.set_time(t0 + Duration::seconds(secs));
+        p
+    }
+    fn square(t0: DateTime<Utc>) -> Trajectory<f64> {
+        Trajectory::from_points(vec![
+            sample(t0, 20, [10.0, 10.0, 0.0]),
+            sample(t0, 0, [0.0, 0.0, 0.0]),
+            sample(t0, 10, [0.0, 10.0, 0.0]),
+        ])
+    }
+    #[test]
+    fn ordered_by_time() {
+        let t0 = Utc::now();
+        let mut tr = square(t0);
+        tr.push(sample(t0, 5, [0.0, 5.0, 0.0]));
+        let times: Vec<_> = tr.iter().map(|p| p.get_time()).collect();
+        let mut sorted = times.clone();
+        sorted.sort();
+        assert_eq!(times, sorted);
+        assert_eq!(tr.len(), 4);
+    }
+    #[test]
+    fn linear_interpolation() {
+        let t0 = Utc::now();
+        let tr = square(t0);
+        let p = tr.position_at(t0 + Duration::seconds(15), Interpolation::Linear).unwrap();
+        assert_eq!(p.get_coordinates(), [5.0, 10.0, 0.0]);
+        assert_eq!(p.get_time(), t0 + Duration::seconds(15));
+        assert!(tr.position_at(t0 + Duration::seconds(21), Interpolation::Linear).is_none());
+        assert!(tr.position_at(t0 - Duration::seconds(1), Interpolation::Linear).is_none());
+    }
+    #[test]
+    fn cubic_passes_through_samples() {
+        let t0 = Utc::now();
+        let tr = square(t0);
+        for s in &[0, 10, 20] {
+            let t = t0 + Duration::seconds(*s);
+            let p = tr.position_at(t, Interpolation::Cubic).unwrap();
+            let q = tr.iter().find(|q| q.get_time() == t).unwrap();
+            assert_eq!(p.get_coordinates(), q.get_coordinates());
+        }
+        /*-- straight constant-speed motion stays straight --*/
+        let line = Trajectory::from_points(vec![
+            sample(t0, 0, [0.0, 0.0, 0.0]),
+            sample(t0, 1, [1.0, 2.0, 3.0]),
+            sample(t0, 2, [2.0, 4.0, 6.0]),
+        ]);
+        let p = line.position_at(t0 + Duration::milliseconds(500), Interpolation::Cubic).unwrap();
+        let c = p.get_coordinates();
+        assert!((c[0] - 0.5).abs() < 1e-9 && (c[1] - 1.0).abs() < 1e-9 && (c[2] - 1.5).abs() < 1e-9);
+    }
+    #[test]
+    fn kinematics() {
+        let t0 = Utc::now();
+        let tr = square(t0);
+        assert_eq!(tr.speed_at(t0 + Duration::seconds(5)), Some(1.0));
+        assert_eq!(tr.heading_at(t0 + Duration::seconds(5)), Some(0.0));
+        assert_eq!(tr.heading_at(t0 + Duration::seconds(15)), Some(90.0));
+        assert_eq!(tr.path_length(), 20.0);
+    }
+}
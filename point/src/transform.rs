@@ -0,0 +1,453 @@
+/////////////////////////////////////////////////////////////
+// point::transform.rs - Rotations and affine transforms   //
+/////////////////////////////////////////////////////////////
+/*
+   Transforms that move Point<T>, or any SpaceTime<T>
+   implementor, between frames, e.g., body and world frames
+   of a vehicle.
+   - Mat3<T>:           3x3 linear map, rotation or scale
+   - Mat4<T>:           4x4 affine or projective map on
+                        homogeneous coordinates
+   - Quaternion<T>:     rotation, Hamilton convention
+   - RigidTransform<T>: rotation followed by translation
+   Every type implements the Transform<T> trait, composes with
+   `*` (a * b applies b first, then a), and has an inverse.
+
+   Angles are radians.  Euler angles are roll about x, pitch
+   about y, and yaw about z, applied in the aerospace order:
+   yaw first, then pitch, then roll, so the body-to-world
+   rotation is Rz(yaw) * Ry(pitch) * Rx(roll).
+*/
+use super::{Point, SpaceTime};
+use num_traits::Float;
+use std::fmt::Debug;
+use std::ops::Mul;
+
+/*-----------------------------------------------------------
+  declare Transform<T> trait
+  - apply changes coordinates in place, keeping time and name
+*/
+pub trait Transform<T> {
+    fn transform_coordinates(&self, c: &[T; 3]) -> [T; 3];
+    fn apply<S>(&self, st: &mut S)
+    where S: SpaceTime<T> + ?Sized, Self: Sized {
+        let c = self.transform_coordinates(&st.get_coordinates());
+        st.set_coordinates(&c);
+    }
+    fn transformed(&self, pt: &Point<T>) -> Point<T>
+    where T: Default + Debug + Clone, Self: Sized {
+        let mut p = pt.clone();
+        self.apply(&mut p);
+        p
+    }
+}
+
+/*-- define Mat3<T> type, row major --*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat3<T> {
+    pub m: [[T; 3]; 3],
+}
+impl<T: Float> Mat3<T> {
+    pub fn from_rows(m: [[T; 3]; 3]) -> Mat3<T> {
+        Mat3 { m }
+    }
+    pub fn identity() -> Mat3<T> {
+        Mat3::scaling(T::one(), T::one(), T::one())
+    }
+    pub fn scaling(sx: T, sy: T, sz: T) -> Mat3<T> {
+        let o = T::zero();
+        Mat3 { m: [[sx, o, o], [o, sy, o], [o, o, sz]] }
+    }
+    pub fn rotation_x(a: T) -> Mat3<T> {
+        let (s, c) = a.sin_cos();
+        let (o, l) = (T::zero(), T::one());
+        Mat3 { m: [[l, o, o], [o, c, -s], [o, s, c]] }
+    }
+    pub fn rotation_y(a: T) -> Mat3<T> {
+        let (s, c) = a.sin_cos();
+        let (o, l) = (T::zero(), T::one());
+        Mat3 { m: [[c, o, s], [o, l, o], [-s, o, c]] }
+    }
+    pub fn rotation_z(a: T) -> Mat3<T> {
+        let (s, c) = a.sin_cos();
+        let (o, l) = (T::zero(), T::one());
+        Mat3 { m: [[c, -s, o], [s, c, o], [o, o, l]] }
+    }
+    /*-- Rz(yaw) * Ry(pitch) * Rx(roll) --*/
+    pub fn from_euler(roll: T, pitch: T, yaw: T) -> Mat3<T> {
+        Mat3::rotation_z(yaw) * Mat3::rotation_y(pitch) * Mat3::rotation_x(roll)
+    }
+    pub fn transpose(&self) -> Mat3<T> {
+        let m = &self.m;
+        Mat3 { m: [[m[0][0], m[1][0], m[2][0]], [m[0][1], m[1][1], m[2][1]], [m[0][2], m[1][2], m[2][2]]] }
+    }
+    pub fn determinant(&self) -> T {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+    /*-- None for a singular matrix --*/
+    pub fn inverse(&self) -> Option<Mat3<T>> {
+        let d = self.determinant();
+        if d == T::zero() || !d.is_finite() {
+            return None;
+        }
+        let m = &self.m;
+        let cof = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+        /*-- adjugate is the transpose of the cofactor matrix --*/
+        let adj = [
+            [cof(1, 2, 1, 2), -cof(0, 2, 1, 2), cof(0, 1, 1, 2)],
+            [-cof(1, 2, 0, 2), cof(0, 2, 0, 2), -cof(0, 1, 0, 2)],
+            [cof(1, 2, 0, 1), -cof(0, 2, 0, 1), cof(0, 1, 0, 1)],
+        ];
+        let mut inv = adj;
+        for row in inv.iter_mut() {
+            for v in row.iter_mut() {
+                *v = *v / d;
+            }
+        }
+        Some(Mat3 { m: inv })
+    }
+}
+impl<T: Float> Mul for Mat3<T> {
+    type Output = Mat3<T>;
+    fn mul(self, rhs: Mat3<T>) -> Mat3<T> {
+        let mut m = [[T::zero(); 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = (0..3).fold(T::zero(), |acc, k| acc + self.m[i][k] * rhs.m[k][j]);
+            }
+        }
+        Mat3 { m }
+    }
+}
+impl<T: Float> Transform<T> for Mat3<T> {
+    fn transform_coordinates(&self, c: &[T; 3]) -> [T; 3] {
+        let r = |i: usize| self.m[i][0] * c[0] + self.m[i][1] * c[1] + self.m[i][2] * c[2];
+        [r(0), r(1), r(2)]
+    }
+}
+
+/*-- define Mat4<T> type, row major, acts on [x, y, z, 1] --*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4<T> {
+    pub m: [[T; 4]; 4],
+}
+impl<T: Float> Mat4<T> {
+    pub fn from_rows(m: [[T; 4]; 4]) -> Mat4<T> {
+        Mat4 { m }
+    }
+    pub fn identity() -> Mat4<T> {
+        Mat4::from_parts(&Mat3::identity(), &[T::zero(); 3])
+    }
+    pub fn translation(t: &[T; 3]) -> Mat4<T> {
+        Mat4::from_parts(&Mat3::identity(), t)
+    }
+    /*-- linear part l followed by translation t --*/
+    pub fn from_parts(l: &Mat3<T>, t: &[T; 3]) -> Mat4<T> {
+        let (o, one) = (T::zero(), T::one());
+        let r = &l.m;
+        Mat4 { m: [
+            [r[0][0], r[0][1], r[0][2], t[0]],
+            [r[1][0], r[1][1], r[1][2], t[1]],
+            [r[2][0], r[2][1], r[2][2], t[2]],
+            [o, o, o, one],
+        ] }
+    }
+    pub fn transpose(&self) -> Mat4<T> {
+        let mut m = [[T::zero(); 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = self.m[j][i];
+            }
+        }
+        Mat4 { m }
+    }
+    /*-- Gauss-Jordan elimination with partial pivoting, None if singular --*/
+    pub fn inverse(&self) -> Option<Mat4<T>> {
+        let mut a = self.m;
+        let mut inv = Mat4::<T>::from_parts(&Mat3::identity(), &[T::zero(); 3]).m;
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|&i, &j| {
+                a[i][col].abs().partial_cmp(&a[j][col].abs()).unwrap_or(std::cmp::Ordering::Equal)
+            })?;
+            if a[pivot][col] == T::zero() || !a[pivot][col].is_finite() {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+            let p = a[col][col];
+            for k in 0..4 {
+                a[col][k] = a[col][k] / p;
+                inv[col][k] = inv[col][k] / p;
+            }
+            for row in 0..4 {
+                if row != col {
+                    let f = a[row][col];
+                    for k in 0..4 {
+                        a[row][k] = a[row][k] - f * a[col][k];
+                        inv[row][k] = inv[row][k] - f * inv[col][k];
+                    }
+                }
+            }
+        }
+        Some(Mat4 { m: inv })
+    }
+    /*-- homogeneous product, w row applied to all four --*/
+    pub fn transform_homogeneous(&self, h: &[T; 4]) -> [T; 4] {
+        let r = |i: usize| (0..4).fold(T::zero(), |acc, k| acc + self.m[i][k] * h[k]);
+        [r(0), r(1), r(2), r(3)]
+    }
+    /*-- direction vector, translation not applied --*/
+    pub fn transform_vector(&self, v: &[T; 3]) -> [T; 3] {
+        let h = self.transform_homogeneous(&[v[0], v[1], v[2], T::zero()]);
+        [h[0], h[1], h[2]]
+    }
+}
+impl<T: Float> Mul for Mat4<T> {
+    type Output = Mat4<T>;
+    fn mul(self, rhs: Mat4<T>) -> Mat4<T> {
+        let mut m = [[T::zero(); 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = (0..4).fold(T::zero(), |acc, k| acc + self.m[i][k] * rhs.m[k][j]);
+            }
+        }
+        Mat4 { m }
+    }
+}
+/*-- points are divided by w after the product --*/
+impl<T: Float> Transform<T> for Mat4<T> {
+    fn transform_coordinates(&self, c: &[T; 3]) -> [T; 3] {
+        let h = self.transform_homogeneous(&[c[0], c[1], c[2], T::one()]);
+        [h[0] / h[3], h[1] / h[3], h[2] / h[3]]
+    }
+}
+
+/*-- define Quaternion<T> type, w + xi + yj + zk --*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion<T> {
+    pub w: T,
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+impl<T: Float> Quaternion<T> {
+    pub fn new(w: T, x: T, y: T, z: T) -> Quaternion<T> {
+        Quaternion { w, x, y, z }
+    }
+    pub fn identity() -> Quaternion<T> {
+        Quaternion::new(T::one(), T::zero(), T::zero(), T::zero())
+    }
+    /*-- rotation by angle about axis, axis need not be unit length --*/
+    pub fn from_axis_angle(axis: &[T; 3], angle: T) -> Option<Quaternion<T>> {
+        let n = (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt();
+        if n == T::zero() || !n.is_finite() {
+            return None;
+        }
+        let two = T::one() + T::one();
+        let (s, c) = (angle / two).sin_cos();
+        Some(Quaternion::new(c, s * axis[0] / n, s * axis[1] / n, s * axis[2] / n))
+    }
+    /*-- same rotation as Mat3::from_euler --*/
+    pub fn from_euler(roll: T, pitch: T, yaw: T) -> Quaternion<T> {
+        let two = T::one() + T::one();
+        let (sr, cr) = (roll / two).sin_cos();
+        let (sp, cp) = (pitch / two).sin_cos();
+        let (sy, cy) = (yaw / two).sin_cos();
+        Quaternion::new(
+            cr * cp * cy + sr * sp * sy,
+            sr * cp * cy - cr * sp * sy,
+            cr * sp * cy + sr * cp * sy,
+            cr * cp * sy - sr * sp * cy,
+        )
+    }
+    /*-- (roll, pitch, yaw), pitch in [-pi/2, pi/2] --*/
+    pub fn to_euler(&self) -> (T, T, T) {
+        let q = self.normalize().unwrap_or_else(Quaternion::identity);
+        let (one, two) = (T::one(), T::one() + T::one());
+        let roll = (two * (q.w * q.x + q.y * q.z)).atan2(one - two * (q.x * q.x + q.y * q.y));
+        let sp = (two * (q.w * q.y - q.z * q.x)).max(-one).min(one);
+        let pitch = sp.asin();
+        let yaw = (two * (q.w * q.z + q.x * q.y)).atan2(one - two * (q.y * q.y + q.z * q.z));
+        (roll, pitch, yaw)
+    }
+    pub fn norm(&self) -> T {
+        (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+    pub fn normalize(&self) -> Option<Quaternion<T>> {
+        let n = self.norm();
+        if n == T::zero() || !n.is_finite() {
+            return None;
+        }
+        Some(Quaternion::new(self.w / n, self.x / n, self.y / n, self.z / n))
+    }
+    pub fn conjugate(&self) -> Quaternion<T> {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+    /*-- None for the zero quaternion --*/
+    pub fn inverse(&self) -> Option<Quaternion<T>> {
+        let n2 = self.norm() * self.norm();
+        if n2 == T::zero() {
+            return None;
+        }
+        let c = self.conjugate();
+        Some(Quaternion::new(c.w / n2, c.x / n2, c.y / n2, c.z / n2))
+    }
+    /*-- rotation matrix of a unit quaternion --*/
+    pub fn to_mat3(&self) -> Mat3<T> {
+        let q = self.normalize().unwrap_or_else(Quaternion::identity);
+        let (one, two) = (T::one(), T::one() + T::one());
+        let (w, x, y, z) = (q.w, q.x, q.y, q.z);
+        Mat3 { m: [
+            [one - two * (y * y + z * z), two * (x * y - w * z), two * (x * z + w * y)],
+            [two * (x * y + w * z), one - two * (x * x + z * z), two * (y * z - w * x)],
+            [two * (x * z - w * y), two * (y * z + w * x), one - two * (x * x + y * y)],
+        ] }
+    }
+    /*-- spherical interpolation, self at s = 0, other at s = 1 --*/
+    pub fn slerp(&self, other: &Quaternion<T>, s: T) -> Quaternion<T> {
+        let a = self.normalize().unwrap_or_else(Quaternion::identity);
+        let mut b = other.normalize().unwrap_or_else(Quaternion::identity);
+        let mut d = a.w * b.w + a.x * b.x + a.y * b.y + a.z * b.z;
+        /*-- take the short way around --*/
+        if d < T::zero() {
+            b = Quaternion::new(-b.w, -b.x, -b.y, -b.z);
+            d = -d;
+        }
+        let (k0, k1) = if d > T::from(0.9995).unwrap() {
+            (T::one() - s, s)
+        } else {
+            let th = d.acos();
+            let st = th.sin();
+            (((T::one() - s) * th).sin() / st, (s * th).sin() / st)
+        };
+        let q = Quaternion::new(k0 * a.w + k1 * b.w, k0 * a.x + k1 * b.x, k0 * a.y + k1 * b.y, k0 * a.z + k1 * b.z);
+        q.normalize().unwrap_or_else(Quaternion::identity)
+    }
+}
+/*-- Hamilton product, a * b rotates by b, then by a --*/
+impl<T: Float> Mul for Quaternion<T> {
+    type Output = Quaternion<T>;
+    fn mul(self, r: Quaternion<T>) -> Quaternion<T> {
+        let q = self;
+        Quaternion::new(
+            q.w * r.w - q.x * r.x - q.y * r.y - q.z * r.z,
+            q.w * r.x + q.x * r.w + q.y * r.z - q.z * r.y,
+            q.w * r.y - q.x * r.z + q.y * r.w + q.z * r.x,
+            q.w * r.z + q.x * r.y - q.y * r.x + q.z * r.w,
+        )
+    }
+}
+impl<T: Float> Transform<T> for Quaternion<T> {
+    fn transform_coordinates(&self, c: &[T; 3]) -> [T; 3] {
+        self.to_mat3().transform_coordinates(c)
+    }
+}
+
+/*-----------------------------------------------------------
+  define RigidTransform<T> type
+  - p -> rotation(p) + translation, no scaling or shear
+  - body-to-world uses the body's attitude as rotation and
+    its world position as translation, its inverse is
+    world-to-body
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RigidTransform<T> {
+    pub rotation: Quaternion<T>,
+    pub translation: [T; 3],
+}
+impl<T: Float> RigidTransform<T> {
+    pub fn new(rotation: Quaternion<T>, translation: [T; 3]) -> RigidTransform<T> {
+        let rotation = rotation.normalize().unwrap_or_else(Quaternion::identity);
+        RigidTransform { rotation, translation }
+    }
+    pub fn identity() -> RigidTransform<T> {
+        RigidTransform::new(Quaternion::identity(), [T::zero(); 3])
+    }
+    pub fn inverse(&self) -> RigidTransform<T> {
+        let r = self.rotation.conjugate();
+        let t = r.transform_coordinates(&self.translation);
+        RigidTransform { rotation: r, translation: [-t[0], -t[1], -t[2]] }
+    }
+    pub fn to_mat4(&self) -> Mat4<T> {
+        Mat4::from_parts(&self.rotation.to_mat3(), &self.translation)
+    }
+}
+/*-- a * b applies b, then a --*/
+impl<T: Float> Mul for RigidTransform<T> {
+    type Output = RigidTransform<T>;
+    fn mul(self, rhs: RigidTransform<T>) -> RigidTransform<T> {
+        let t = self.transform_coordinates(&rhs.translation);
+        RigidTransform::new(self.rotation * rhs.rotation, t)
+    }
+}
+impl<T: Float> Transform<T> for RigidTransform<T> {
+    fn transform_coordinates(&self, c: &[T; 3]) -> [T; 3] {
+        let r = self.rotation.transform_coordinates(c);
+        [r[0] + self.translation[0], r[1] + self.translation[1], r[2] + self.translation[2]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+    fn close(a: &[f64; 3], b: &[f64; 3]) -> bool {
+        (0..3).all(|k| (a[k] - b[k]).abs() < 1e-9)
+    }
+    #[test]
+    fn matrix_rotations() {
+        let rz = Mat3::rotation_z(FRAC_PI_2);
+        assert!(close(&rz.transform_coordinates(&[1.0, 0.0, 0.0]), &[0.0, 1.0, 0.0]));
+        let inv = rz.inverse().unwrap();
+        assert!(close(&(inv * rz).transform_coordinates(&[1.0, 2.0, 3.0]), &[1.0, 2.0, 3.0]));
+        assert_eq!(inv, rz.transpose());
+        assert!(Mat3::scaling(1.0, 0.0, 1.0).inverse().is_none());
+    }
+    #[test]
+    fn quaternion_matches_matrix() {
+        let (r, p, y) = (0.3, -0.7, 2.1);
+        let q = Quaternion::from_euler(r, p, y);
+        let m = Mat3::from_euler(r, p, y);
+        let v = [1.0, -2.0, 0.5];
+        assert!(close(&q.transform_coordinates(&v), &m.transform_coordinates(&v)));
+        let (r2, p2, y2) = q.to_euler();
+        assert!(close(&[r2, p2, y2], &[r, p, y]));
+        let qz = Quaternion::from_axis_angle(&[0.0, 0.0, 2.0], FRAC_PI_2).unwrap();
+        let qx = Quaternion::from_axis_angle(&[1.0, 0.0, 0.0], FRAC_PI_2).unwrap();
+        let both = qz * qx;
+        let mboth = Mat3::rotation_z(FRAC_PI_2) * Mat3::rotation_x(FRAC_PI_2);
+        assert!(close(&both.transform_coordinates(&v), &mboth.transform_coordinates(&v)));
+        let back = both.inverse().unwrap() * both;
+        assert!(close(&back.transform_coordinates(&v), &v));
+        let half = Quaternion::identity().slerp(&qz, 0.5);
+        assert!(close(&half.transform_coordinates(&[1.0, 0.0, 0.0]), &[0.5f64.sqrt(), 0.5f64.sqrt(), 0.0]));
+    }
+    #[test]
+    fn rigid_body_and_world() {
+        /*-- vehicle at (10, 0, 0) heading along +y --*/
+        let body_to_world = RigidTransform::new(Quaternion::from_euler(0.0, 0.0, FRAC_PI_2), [10.0, 0.0, 0.0]);
+        let mut pt = Point::<f64>::new();
+        pt.set_coordinates(&[1.0, 0.0, 0.0]);
+        pt.set_name("nose");
+        let w = body_to_world.transformed(&pt);
+        assert!(close(&w.get_coordinates(), &[10.0, 1.0, 0.0]));
+        assert_eq!(w.get_name(), "nose");
+        assert_eq!(w.get_time(), pt.get_time());
+        let world_to_body = body_to_world.inverse();
+        assert!(close(&world_to_body.transformed(&w).get_coordinates(), &[1.0, 0.0, 0.0]));
+        let id = world_to_body * body_to_world;
+        assert!(close(&id.transform_coordinates(&[4.0, 5.0, 6.0]), &[4.0, 5.0, 6.0]));
+        /*-- same transform as a 4x4 matrix --*/
+        let m = body_to_world.to_mat4();
+        assert!(close(&m.transform_coordinates(&[1.0, 0.0, 0.0]), &[10.0, 1.0, 0.0]));
+        let mi = m.inverse().unwrap();
+        assert!(close(&(mi * m).transform_coordinates(&[4.0, 5.0, 6.0]), &[4.0, 5.0, 6.0]));
+        assert!(close(&m.transform_vector(&[1.0, 0.0, 0.0]), &[0.0, 1.0, 0.0]));
+        let mut dynpt: Box<dyn SpaceTime<f64>> = Box::new(pt);
+        Mat4::translation(&[1.0, 1.0, 1.0]).apply(dynpt.as_mut());
+        assert!(close(&dynpt.get_coordinates(), &[2.0, 1.0, 1.0]));
+    }
+}
@@ -0,0 +1,217 @@
+/////////////////////////////////////////////////////////////
+// point::units.rs - Unit-tagged lengths for coordinates   //
+/////////////////////////////////////////////////////////////
+/*
+   Length<T, U> is a number T tagged, at compile time only,
+   with a unit U: Meters, Feet, or NauticalMiles.  It is the
+   same size as T.
+   - Add and Sub need the same unit on both sides, so adding
+     Feet to Meters does not compile.
+   - Mul and Div by a plain T scale a length.
+   - to::<V>() converts floating point lengths.  try_to::<V>()
+     works for any numeric T and returns None when the result
+     can't be held in T, e.g., overflow or a fraction in an
+     integer type.
+
+   Point<Length<T, U>> is a point with unit-tagged coordinates,
+   UnitPoint<T, U> for short.  It has the SpaceTime trait and
+   the Add, Sub, and Neg operators of every Point, so points
+   in different units can't be mixed either.  tag, untag, and
+   convert move between plain and tagged points.  Plain
+   Point<T> is unchanged.
+*/
+use super::{Point, SpaceTime};
+use num_traits::{Float, NumCast, ToPrimitive};
+use std::fmt::{self, Debug, Display};
+use std::marker::PhantomData;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/*-- marker for a unit of length --*/
+pub trait Unit: Copy + Default + Debug + PartialEq + PartialOrd {
+    const METERS_PER_UNIT: f64;
+    const SYMBOL: &'static str;
+}
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Meters;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Feet;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NauticalMiles;
+impl Unit for Meters {
+    const METERS_PER_UNIT: f64 = 1.0;
+    const SYMBOL: &'static str = "m";
+}
+/*-- international foot --*/
+impl Unit for Feet {
+    const METERS_PER_UNIT: f64 = 0.3048;
+    const SYMBOL: &'static str = "ft";
+}
+impl Unit for NauticalMiles {
+    const METERS_PER_UNIT: f64 = 1852.0;
+    const SYMBOL: &'static str = "NM";
+}
+
+/*-- define Length<T, U> type --*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct Length<T, U> {
+    value: T,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    unit: PhantomData<U>,
+}
+impl<T, U: Unit> Length<T, U> {
+    pub fn new(value: T) -> Length<T, U> {
+        Length { value, unit: PhantomData }
+    }
+    pub fn value(&self) -> T where T: Clone {
+        self.value.clone()
+    }
+    pub fn into_value(self) -> T {
+        self.value
+    }
+    pub fn symbol(&self) -> &'static str {
+        U::SYMBOL
+    }
+}
+impl<T: NumCast + ToPrimitive, U: Unit> Length<T, U> {
+    /*-- None if the converted value doesn't fit T exactly enough --*/
+    pub fn try_to<V: Unit>(&self) -> Option<Length<T, V>> {
+        let v = self.value.to_f64()? * U::METERS_PER_UNIT / V::METERS_PER_UNIT;
+        if !v.is_finite() {
+            return None;
+        }
+        let out = T::from(v)?;
+        /*-- reject truncation, e.g., 1 ft in integer meters --*/
+        let back = out.to_f64()?;
+        if (back - v).abs() > 1e-9 * v.abs().max(1.0) {
+            return None;
+        }
+        Some(Length::new(out))
+    }
+}
+impl<T: Float, U: Unit> Length<T, U> {
+    pub fn to<V: Unit>(&self) -> Length<T, V> {
+        let k = T::from(U::METERS_PER_UNIT / V::METERS_PER_UNIT).unwrap();
+        Length::new(self.value * k)
+    }
+}
+impl<T: Display, U: Unit> Display for Length<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.value, U::SYMBOL)
+    }
+}
+impl<T: Add<Output = T>, U: Unit> Add for Length<T, U> {
+    type Output = Length<T, U>;
+    fn add(self, rhs: Length<T, U>) -> Length<T, U> {
+        Length::new(self.value + rhs.value)
+    }
+}
+impl<T: Sub<Output = T>, U: Unit> Sub for Length<T, U> {
+    type Output = Length<T, U>;
+    fn sub(self, rhs: Length<T, U>) -> Length<T, U> {
+        Length::new(self.value - rhs.value)
+    }
+}
+impl<T: Neg<Output = T>, U: Unit> Neg for Length<T, U> {
+    type Output = Length<T, U>;
+    fn neg(self) -> Length<T, U> {
+        Length::new(-self.value)
+    }
+}
+impl<T: Mul<Output = T>, U: Unit> Mul<T> for Length<T, U> {
+    type Output = Length<T, U>;
+    fn mul(self, s: T) -> Length<T, U> {
+        Length::new(self.value * s)
+    }
+}
+impl<T: Div<Output = T>, U: Unit> Div<T> for Length<T, U> {
+    type Output = Length<T, U>;
+    fn div(self, s: T) -> Length<T, U> {
+        Length::new(self.value / s)
+    }
+}
+
+/*-- point whose coordinates carry unit U --*/
+pub type UnitPoint<T, U> = Point<Length<T, U>>;
+
+impl<T, U> Point<Length<T, U>>
+where T: Default + Debug + Clone, U: Unit {
+    /*-- attach unit U to a plain point's coordinates --*/
+    pub fn tag(pt: Point<T>) -> Point<Length<T, U>> {
+        Point { x: Length::new(pt.x), y: Length::new(pt.y), z: Length::new(pt.z), t: pt.t, n: pt.n }
+    }
+    /*-- plain point with the same numbers --*/
+    pub fn untag(self) -> Point<T> {
+        Point { x: self.x.value, y: self.y.value, z: self.z.value, t: self.t, n: self.n }
+    }
+    /*-- same position expressed in unit V --*/
+    pub fn convert<V: Unit>(&self) -> Point<Length<T, V>> where T: Float {
+        Point { x: self.x.to(), y: self.y.to(), z: self.z.to(), t: self.t, n: self.n.clone() }
+    }
+    pub fn try_convert<V: Unit>(&self) -> Option<Point<Length<T, V>>>
+    where T: NumCast + ToPrimitive {
+        Some(Point { x: self.x.try_to()?, y: self.y.try_to()?, z: self.z.try_to()?, t: self.t, n: self.n.clone() })
+    }
+    /*-- straight-line distance, in the points' unit --*/
+    pub fn distance(&self, other: &Point<Length<T, U>>) -> Length<T, U> where T: Float {
+        let a = self.get_coordinates();
+        let b = other.get_coordinates();
+        let d2 = (0..3).fold(T::zero(), |acc, k| {
+            let d = a[k].value - b[k].value;
+            acc + d * d
+        });
+        Length::new(d2.sqrt())
+    }
+}
+/*-- plain point with unit U attached --*/
+impl<T, U> From<Point<T>> for Point<Length<T, U>>
+where T: Default + Debug + Clone, U: Unit {
+    fn from(pt: Point<T>) -> Point<Length<T, U>> {
+        Point::tag(pt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn length_conversions() {
+        let alt = Length::<f64, Feet>::new(10_000.0);
+        let m = alt.to::<Meters>();
+        assert!((m.value() - 3048.0).abs() < 1e-9);
+        assert_eq!(m.to_string(), format!("{} m", m.value()));
+        let nm = Length::<f64, Meters>::new(3704.0).to::<NauticalMiles>();
+        assert!((nm.value() - 2.0).abs() < 1e-12);
+        let sum = m + Length::new(52.0);
+        assert!((sum.value() - 3100.0).abs() < 1e-9);
+        assert!(Length::<f64, Feet>::new(1.0) < Length::new(2.0));
+        assert_eq!((Length::<i32, Meters>::new(6) * 2 / 3).value(), 4);
+    }
+    #[test]
+    fn checked_integer_conversions() {
+        assert_eq!(Length::<i32, NauticalMiles>::new(2).try_to::<Meters>(), Some(Length::new(3704)));
+        assert_eq!(Length::<i32, Feet>::new(1).try_to::<Meters>(), None);
+        assert_eq!(Length::<i32, NauticalMiles>::new(2_000_000).try_to::<Meters>(), None);
+        assert_eq!(Length::<u8, Meters>::new(1).try_to::<Feet>(), None);
+        assert_eq!(Length::<i64, Feet>::new(6_076).try_to::<Feet>(), Some(Length::new(6_076)));
+    }
+    #[test]
+    fn unit_points() {
+        let mut p = Point::<f64>::new();
+        p.set_coordinates(&[0.0, 3.0, 4.0]);
+        p.set_name("fix");
+        let ft: UnitPoint<f64, Feet> = p.clone().into();
+        let m = ft.convert::<Meters>();
+        assert!((m.distance(&Point::tag(Point::new())).value() - 5.0 * 0.3048).abs() < 1e-12);
+        assert_eq!(m.get_name(), "fix");
+        let back = m.convert::<Feet>().untag();
+        assert!((back.get_coordinates()[2] - 4.0).abs() < 1e-12);
+        let moved = &ft + &ft;
+        assert_eq!(moved.get_coordinates()[1], Length::new(6.0));
+        let ip: UnitPoint<i32, Meters> = Point::tag(Point::new());
+        assert!(ip.try_convert::<Feet>().is_some());
+        /*-- &ft + &m does not compile: mismatched types --*/
+    }
+}
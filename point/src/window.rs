@@ -0,0 +1,320 @@
+/////////////////////////////////////////////////////////////
+// point::window.rs - Time-windowed aggregation            //
+/////////////////////////////////////////////////////////////
+/*
+   Groups a stream of SpaceTime objects into time windows by
+   get_time() and summarizes each window with a
+   WindowAggregate: count, first and last positions, mean
+   position, and bounding box.
+   - WindowSpec::tumbling(size):       back to back windows
+   - WindowSpec::sliding(size, step):  a window starts every
+                                       step, so windows
+                                       overlap when step < size
+   A sliding window may be at most MAX_OVERLAP steps long, so
+   an object never falls in more than MAX_OVERLAP windows.
+   Windows are half open, [start, start + size), and aligned
+   to an origin, the Unix epoch unless set, so one-second
+   windows start on whole seconds.  grouped_by_name keeps a
+   separate window for each name.
+
+   Windower takes objects one at a time and returns windows as
+   they close, that is, once an object at or after the window
+   end has been seen.  Objects may arrive a little out of
+   order, but an object whose windows have already closed is
+   dropped and counted in late().  An object about 292 years
+   or more from the origin is dropped and counted in
+   out_of_range().  flush closes the rest.
+   aggregate_windows does the same over an iterator.
+*/
+use super::{Aabb, Named, Point, SpaceTime};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use num_traits::ToPrimitive;
+use std::collections::{BTreeMap, VecDeque};
+
+/*-- most windows one object may fall in, size / step rounded up --*/
+pub const MAX_OVERLAP: i64 = 10_000;
+
+/*-- how to cut time into windows --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowSpec {
+    size: Duration,
+    step: Duration,
+    origin: DateTime<Utc>,
+    by_name: bool,
+}
+impl WindowSpec {
+    pub fn tumbling(size: Duration) -> WindowSpec {
+        WindowSpec::sliding(size, size)
+    }
+    /*-- size and step must be positive, fit in i64 nanoseconds,
+         and size be at most MAX_OVERLAP steps --*/
+    pub fn sliding(size: Duration, step: Duration) -> WindowSpec {
+        assert!(size > Duration::zero() && step > Duration::zero(), "window size and step must be positive");
+        let (size_ns, step_ns) = match (size.num_nanoseconds(), step.num_nanoseconds()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => panic!("window size and step must be under about 292 years"),
+        };
+        assert!(
+            (size_ns - 1) / step_ns < MAX_OVERLAP,
+            "window size must be at most {} steps",
+            MAX_OVERLAP
+        );
+        WindowSpec { size, step, origin: Utc.timestamp_opt(0, 0).unwrap(), by_name: false }
+    }
+    pub fn with_origin(mut self, origin: DateTime<Utc>) -> WindowSpec {
+        self.origin = origin;
+        self
+    }
+    pub fn grouped_by_name(mut self) -> WindowSpec {
+        self.by_name = true;
+        self
+    }
+    /*-----------------------------------------------------------
+      (start, end) of every window holding time t, earliest
+      first, None if t or a window bound is out of range
+    */
+    fn windows(&self, t: DateTime<Utc>) -> Option<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+        let (size, step) = (self.size.num_nanoseconds()?, self.step.num_nanoseconds()?);
+        let off = t.signed_duration_since(self.origin).num_nanoseconds()?;
+        let last = off.div_euclid(step);
+        let first = off.checked_sub(size)?.div_euclid(step) + 1;
+        (first..=last)
+            .map(|k| {
+                let start = self.origin.checked_add_signed(Duration::nanoseconds(k.checked_mul(step)?))?;
+                Some((start, start.checked_add_signed(self.size)?))
+            })
+            .collect()
+    }
+}
+
+/*-- summary of one window --*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowAggregate<T> {
+    /*-- group name, empty unless grouped by name --*/
+    pub name: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub count: usize,
+    pub first: [T; 3],
+    pub first_time: DateTime<Utc>,
+    pub last: [T; 3],
+    pub last_time: DateTime<Utc>,
+    pub mean: [f64; 3],
+    pub bounds: Aabb<T>,
+}
+impl<T> WindowAggregate<T> {
+    /*-- mean position at the window start, for downsampling --*/
+    pub fn mean_point(&self) -> Point<f64> {
+        let mut pt = Point::new();
+        pt.set_coordinates(&self.mean);
+        pt.set_time(self.start);
+        pt.set_name(&self.name);
+        pt
+    }
+}
+
+/*-- running totals of an open window --*/
+#[derive(Debug, Clone)]
+struct Acc<T> {
+    agg: WindowAggregate<T>,
+    sum: [f64; 3],
+}
+impl<T: Copy + PartialOrd + ToPrimitive> Acc<T> {
+    fn new(name: &str, start: DateTime<Utc>, end: DateTime<Utc>, c: [T; 3], t: DateTime<Utc>) -> Acc<T> {
+        Acc {
+            agg: WindowAggregate {
+                name: name.to_string(), start, end, count: 0,
+                first: c, first_time: t, last: c, last_time: t,
+                mean: [0.0; 3], bounds: Aabb::from_coordinates(&c),
+            },
+            sum: [0.0; 3],
+        }
+    }
+    fn add(&mut self, c: [T; 3], t: DateTime<Utc>) {
+        let a = &mut self.agg;
+        a.count += 1;
+        if t < a.first_time {
+            a.first = c;
+            a.first_time = t;
+        }
+        if t >= a.last_time {
+            a.last = c;
+            a.last_time = t;
+        }
+        a.bounds.extend(&c);
+        for (s, v) in self.sum.iter_mut().zip(c.iter()) {
+            *s += v.to_f64().unwrap_or(f64::NAN);
+        }
+    }
+    fn finish(mut self) -> WindowAggregate<T> {
+        let n = self.agg.count as f64;
+        self.agg.mean = [self.sum[0] / n, self.sum[1] / n, self.sum[2] / n];
+        self.agg
+    }
+}
+
+/*-- define Windower<T> type --*/
+#[derive(Debug, Clone)]
+pub struct Windower<T> {
+    spec: WindowSpec,
+    /*-- open windows, keyed by start then name --*/
+    open: BTreeMap<(DateTime<Utc>, String), Acc<T>>,
+    watermark: Option<DateTime<Utc>>,
+    late: usize,
+    out_of_range: usize,
+}
+impl<T: Copy + PartialOrd + ToPrimitive> Windower<T> {
+    pub fn new(spec: WindowSpec) -> Windower<T> {
+        Windower { spec, open: BTreeMap::new(), watermark: None, late: 0, out_of_range: 0 }
+    }
+    /*-- objects dropped because their windows had closed --*/
+    pub fn late(&self) -> usize {
+        self.late
+    }
+    /*-- objects dropped for being too far from the origin --*/
+    pub fn out_of_range(&self) -> usize {
+        self.out_of_range
+    }
+    pub fn push<S>(&mut self, st: &S) -> Vec<WindowAggregate<T>>
+    where S: SpaceTime<T> + Named + ?Sized {
+        self.push_as(st.get_name(), st)
+    }
+    /*-- add one object under name, returns windows closed by its time --*/
+    pub fn push_as<S>(&mut self, name: &str, st: &S) -> Vec<WindowAggregate<T>>
+    where S: SpaceTime<T> + ?Sized {
+        let (t, c) = (st.get_time(), st.get_coordinates());
+        let key = if self.spec.by_name { name } else { "" };
+        let windows = match self.spec.windows(t) {
+            Some(w) => w,
+            None => {
+                self.out_of_range += 1;
+                return Vec::new();
+            }
+        };
+        let mut accepted = false;
+        for (start, end) in windows {
+            if self.watermark.is_some_and(|w| w >= end) {
+                continue;
+            }
+            accepted = true;
+            self.open.entry((start, key.to_string()))
+                .or_insert_with(|| Acc::new(key, start, end, c, t))
+                .add(c, t);
+        }
+        if !accepted {
+            self.late += 1;
+        }
+        if self.watermark.map_or(true, |w| t > w) {
+            self.watermark = Some(t);
+        }
+        self.close(false)
+    }
+    /*-- close every open window --*/
+    pub fn flush(&mut self) -> Vec<WindowAggregate<T>> {
+        self.close(true)
+    }
+    fn close(&mut self, all: bool) -> Vec<WindowAggregate<T>> {
+        let w = self.watermark;
+        let done: Vec<(DateTime<Utc>, String)> = self.open.iter()
+            .filter(|(_, a)| all || w.is_some_and(|w| w >= a.agg.end))
+            .map(|(k, _)| k.clone())
+            .collect();
+        done.into_iter().filter_map(|k| self.open.remove(&k)).map(Acc::finish).collect()
+    }
+}
+
+/*-- iterator of aggregates, see aggregate_windows --*/
+pub struct WindowIter<I, T> {
+    inner: I,
+    windower: Windower<T>,
+    ready: VecDeque<WindowAggregate<T>>,
+    done: bool,
+}
+impl<I, S, T> Iterator for WindowIter<I, T>
+where I: Iterator<Item = S>, S: SpaceTime<T> + Named, T: Copy + PartialOrd + ToPrimitive {
+    type Item = WindowAggregate<T>;
+    fn next(&mut self) -> Option<WindowAggregate<T>> {
+        loop {
+            if let Some(a) = self.ready.pop_front() {
+                return Some(a);
+            }
+            if self.done {
+                return None;
+            }
+            match self.inner.next() {
+                Some(st) => self.ready.extend(self.windower.push(&st)),
+                None => {
+                    self.done = true;
+                    self.ready.extend(self.windower.flush());
+                }
+            }
+        }
+    }
+}
+/*-- windows of a time-ordered stream, closing all at its end --*/
+pub fn aggregate_windows<I, S, T>(items: I, spec: WindowSpec) -> WindowIter<I::IntoIter, T>
+where I: IntoIterator<Item = S>, S: SpaceTime<T> + Named, T: Copy + PartialOrd + ToPrimitive {
+    WindowIter { inner: items.into_iter(), windower: Windower::new(spec), ready: VecDeque::new(), done: false }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn pt(name: &str, x: i32, ms: i64) -> Point<i32> {
+        let mut p = Point::new();
+        p.set_coordinates(&[x, -x, 0]);
+        p.set_time(Utc.timestamp_opt(1_000, 0).unwrap() + Duration::milliseconds(ms));
+        p.set_name(name);
+        p
+    }
+    #[test]
+    fn tumbling_seconds() {
+        let pts = vec![pt("a", 1, 0), pt("a", 3, 400), pt("a", 5, 999), pt("a", 7, 1500), pt("a", 9, 3200)];
+        let w: Vec<WindowAggregate<i32>> = aggregate_windows(pts, WindowSpec::tumbling(Duration::seconds(1))).collect();
+        assert_eq!(w.len(), 3);
+        assert_eq!((w[0].count, w[0].first, w[0].last), (3, [1, -1, 0], [5, -5, 0]));
+        assert_eq!(w[0].mean, [3.0, -3.0, 0.0]);
+        assert_eq!(w[0].bounds, Aabb { min: [1, -5, 0], max: [5, -1, 0] });
+        assert_eq!(w[0].start, Utc.timestamp_opt(1_000, 0).unwrap());
+        assert_eq!(w[1].end, Utc.timestamp_opt(1_002, 0).unwrap());
+        assert_eq!(w[2].start, Utc.timestamp_opt(1_003, 0).unwrap());
+        assert_eq!(w[2].mean_point().get_coordinates(), [9.0, -9.0, 0.0]);
+    }
+    #[test]
+    fn sliding_and_grouped() {
+        let spec = WindowSpec::sliding(Duration::seconds(2), Duration::seconds(1)).grouped_by_name();
+        let mut win = Windower::new(spec);
+        assert!(win.push(&pt("a", 1, 500)).is_empty());
+        assert!(win.push(&pt("b", 2, 700)).is_empty());
+        /*-- time 1.2 closes [-1, 1) for a and b --*/
+        let closed = win.push(&pt("a", 3, 1200));
+        let keys: Vec<(&str, usize)> = closed.iter().map(|a| (a.name.as_str(), a.count)).collect();
+        assert_eq!(keys, [("a", 1), ("b", 1)]);
+        let rest = win.flush();
+        let keys: Vec<(&str, i64, usize)> = rest.iter()
+            .map(|a| (a.name.as_str(), a.start.timestamp() - 1_000, a.count))
+            .collect();
+        assert_eq!(keys, [("a", 0, 2), ("b", 0, 1), ("a", 1, 1)]);
+    }
+    #[test]
+    fn late_objects_dropped() {
+        let mut win = Windower::new(WindowSpec::tumbling(Duration::seconds(1)));
+        win.push(&pt("a", 1, 100));
+        assert_eq!(win.push(&pt("a", 2, 2100)).len(), 1);
+        /*-- [1000, 1001) is closed, [1002, 1003) is still open --*/
+        assert!(win.push(&pt("a", 3, 900)).is_empty());
+        win.push_as("other", &pt("a", 4, 2050));
+        assert_eq!(win.late(), 1);
+        /*-- far from the origin: counted, not misplaced --*/
+        let mut far = pt("a", 5, 0);
+        far.set_time(Utc.timestamp_opt(1_000, 0).unwrap() + Duration::days(365 * 300));
+        assert!(win.push(&far).is_empty());
+        assert_eq!((win.out_of_range(), win.late()), (1, 1));
+        assert!(std::panic::catch_unwind(|| WindowSpec::tumbling(Duration::days(365 * 300))).is_err());
+        assert!(std::panic::catch_unwind(|| WindowSpec::sliding(Duration::hours(1), Duration::nanoseconds(1))).is_err());
+        let edge = WindowSpec::sliding(Duration::nanoseconds(MAX_OVERLAP), Duration::nanoseconds(1));
+        assert_eq!(edge.windows(Utc.timestamp_opt(5, 0).unwrap()).unwrap().len(), MAX_OVERLAP as usize);
+        let last = win.flush();
+        assert_eq!((last.len(), last[0].count, last[0].first), (1, 2, [4, -4, 0]));
+    }
+}
@@ -1,38 +1,376 @@
 #![allow(dead_code)]
 #![allow(clippy::print_literal)]
+/*
+   TestLifeCycle reports its life cycle events, Created,
+   Cloned, and Dropped, to a Recorder.  Tracked<T> does the
+   same for any T, and can also report Moved.
+   - StdoutRecorder prints each event, as TestLifeCycle always
+     has.  It is used by new and param_new.
+   - EventLog keeps events in memory so tests can assert on
+     the exact order of constructs, clones, and drops.  It is
+     thread-safe, so instances may be shared with Arc and
+     dropped on other threads.
+   Each instance has a process-wide unique id, and each event
+   a process-wide sequence number.  Clones report to the same
+   recorder as the instance they were cloned from.
 
+   counts() returns process-wide totals of created, cloned,
+   dropped, and live instances of both types.  Tests run in
+   parallel, so a test should check with a LeakGuard instead:
+   - instances created or cloned on the guard's thread while
+     it is the innermost guard there belong to it, wherever
+     they are dropped
+   - check() lists any of them still alive, or dropped twice
+   - when the guard goes out of scope with problems it panics,
+     or with LeakGuard::reporting() prints them to stderr
+   - a guard is bound to the thread that made it, so it is
+     not Send
+   That finds Rc cycles and forgotten Boxes without reading
+   printed output.
+*/
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static NEXT_SEQ: AtomicU64 = AtomicU64::new(1);
+static TOTALS: Mutex<Tally> = Mutex::new(Tally::new());
+const TEST_LIFE_CYCLE: &str = "TestLifeCycle";
+
+thread_local! {
+    /*-- active guards on this thread, innermost last --*/
+    static GUARDS: RefCell<Vec<Arc<Mutex<Tally>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/*-- instance counts, process-wide or for one LeakGuard --*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub created: u64,
+    pub cloned: u64,
+    pub dropped: u64,
+    pub dropped_twice: u64,
+}
+impl Counts {
+    pub fn live(&self) -> u64 {
+        self.created + self.cloned - self.dropped
+    }
+}
+/*-- counts plus ids of live and twice dropped instances --*/
+#[derive(Debug)]
+struct Tally {
+    counts: Counts,
+    live: BTreeSet<u64>,
+    twice: Vec<u64>,
+}
+impl Tally {
+    const fn new() -> Tally {
+        Tally {
+            counts: Counts { created: 0, cloned: 0, dropped: 0, dropped_twice: 0 },
+            live: BTreeSet::new(),
+            twice: Vec::new(),
+        }
+    }
+    fn born(&mut self, id: u64, cloned: bool) {
+        if cloned {
+            self.counts.cloned += 1;
+        } else {
+            self.counts.created += 1;
+        }
+        self.live.insert(id);
+    }
+    fn died(&mut self, id: u64) {
+        if self.live.remove(&id) {
+            self.counts.dropped += 1;
+        } else {
+            self.counts.dropped_twice += 1;
+            self.twice.push(id);
+        }
+    }
+}
+/*-- a panic while holding a lock leaves the data usable --*/
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+/*-- process-wide counts, all threads and tests --*/
+pub fn counts() -> Counts {
+    lock(&TOTALS).counts
+}
+pub fn live_count() -> u64 {
+    counts().live()
+}
+fn current_guard() -> Option<Arc<Mutex<Tally>>> {
+    GUARDS.with(|g| g.borrow().last().cloned())
+}
+
+/*-- what a LeakGuard found --*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeakReport {
+    pub counts: Counts,
+    /*-- ids of instances still alive --*/
+    pub leaked: Vec<u64>,
+    pub dropped_twice: Vec<u64>,
+}
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "TestLifeCycle: {} leaked {:?}, {} dropped twice {:?}",
+            self.leaked.len(), self.leaked, self.dropped_twice.len(), self.dropped_twice
+        )
+    }
+}
+impl std::error::Error for LeakReport {}
+
+/*-- scope guard that checks for leaks when it goes out of scope --*/
+#[derive(Debug)]
+pub struct LeakGuard {
+    tally: Arc<Mutex<Tally>>,
+    panics: bool,
+    armed: bool,
+    /*-- not Send, it must be dropped where GUARDS holds it --*/
+    thread: PhantomData<*const ()>,
+}
+impl LeakGuard {
+    /*-- panics at end of scope if there are problems --*/
+    pub fn new() -> LeakGuard {
+        let tally = Arc::new(Mutex::new(Tally::new()));
+        GUARDS.with(|g| g.borrow_mut().push(Arc::clone(&tally)));
+        LeakGuard { tally, panics: true, armed: true, thread: PhantomData }
+    }
+    /*-- prints problems to stderr instead of panicking --*/
+    pub fn reporting() -> LeakGuard {
+        let mut guard = LeakGuard::new();
+        guard.panics = false;
+        guard
+    }
+    pub fn counts(&self) -> Counts {
+        lock(&self.tally).counts
+    }
+    pub fn check(&self) -> Result<Counts, LeakReport> {
+        let t = lock(&self.tally);
+        if t.live.is_empty() && t.twice.is_empty() {
+            Ok(t.counts)
+        } else {
+            Err(LeakReport {
+                counts: t.counts,
+                leaked: t.live.iter().cloned().collect(),
+                dropped_twice: t.twice.clone(),
+            })
+        }
+    }
+    /*-- check now and end the guard without panicking --*/
+    pub fn finish(mut self) -> Result<Counts, LeakReport> {
+        self.armed = false;
+        self.check()
+    }
+}
+impl Default for LeakGuard {
+    fn default() -> LeakGuard {
+        LeakGuard::new()
+    }
+}
+impl Drop for LeakGuard {
+    fn drop(&mut self) {
+        GUARDS.with(|g| g.borrow_mut().retain(|t| !Arc::ptr_eq(t, &self.tally)));
+        if !self.armed {
+            return;
+        }
+        if let Err(report) = self.check() {
+            if self.panics && !std::thread::panicking() {
+                panic!("{}", report);
+            }
+            eprint!("\n  {}", report);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Created,
+    Cloned,
+    Dropped,
+    Moved,
+}
+/*-- one life cycle event --*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub seq: u64,
+    pub kind: EventKind,
+    /*-- id of the instance the event is about --*/
+    pub id: u64,
+    /*-- for Cloned, id of the instance cloned from --*/
+    pub source: Option<u64>,
+    /*-- TestLifeCycle's count, 0 for Tracked<T> --*/
+    pub value: u32,
+    /*-- "TestLifeCycle", or the full name of a Tracked<T> --*/
+    pub type_name: &'static str,
+}
+
+/*-- sink for life cycle events --*/
+pub trait Recorder: Send + Sync {
+    fn record(&self, event: &Event);
+}
+/*-- prints events to stdout --*/
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutRecorder;
+impl Recorder for StdoutRecorder {
+    fn record(&self, event: &Event) {
+        /*-- a Tracked<T> has no count, so it is shown by id --*/
+        let name = event.type_name;
+        let n = if name == TEST_LIFE_CYCLE { u64::from(event.value) } else { event.id };
+        match event.kind {
+            EventKind::Created => print!("\n  creating instance {} of {}", n, name),
+            EventKind::Cloned => print!("\n  {} instance {} cloned", name, n),
+            EventKind::Dropped => print!("\n  {} instance {} dropped", name, n),
+            EventKind::Moved => print!("\n  {} instance {} moved", name, n),
+        }
+    }
+}
+/*-- in-memory, thread-safe event log --*/
 #[derive(Debug, Default)]
+pub struct EventLog {
+    events: Mutex<Vec<Event>>,
+}
+impl EventLog {
+    pub fn new() -> Arc<EventLog> {
+        Arc::new(EventLog::default())
+    }
+    /*-- recorded events, in sequence order --*/
+    pub fn events(&self) -> Vec<Event> {
+        let mut evs = self.lock().clone();
+        evs.sort_by_key(|e| e.seq);
+        evs
+    }
+    /*-- (kind, id) pairs, in sequence order --*/
+    pub fn kinds(&self) -> Vec<(EventKind, u64)> {
+        self.events().iter().map(|e| (e.kind, e.id)).collect()
+    }
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+    fn lock(&self) -> MutexGuard<'_, Vec<Event>> {
+        lock(&self.events)
+    }
+}
+impl Recorder for EventLog {
+    fn record(&self, event: &Event) {
+        self.lock().push(*event);
+    }
+}
+
+/*-- instrumentation shared by TestLifeCycle and Tracked<T> --*/
+struct Life {
+    id: u64,
+    type_name: &'static str,
+    recorder: Arc<dyn Recorder>,
+    guard: Option<Arc<Mutex<Tally>>>,
+}
+impl Life {
+    fn new(type_name: &'static str, recorder: Arc<dyn Recorder>, value: u32) -> Life {
+        let life = Life::unannounced(type_name, recorder);
+        life.emit(EventKind::Created, None, value);
+        life
+    }
+    /*-- counted, but no Created event --*/
+    fn unannounced(type_name: &'static str, recorder: Arc<dyn Recorder>) -> Life {
+        let life = Life { id: NEXT_ID.fetch_add(1, Ordering::SeqCst), type_name, recorder, guard: current_guard() };
+        life.register(false);
+        life
+    }
+    fn clone_with(&self, value: u32) -> Life {
+        let life = Life {
+            id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+            type_name: self.type_name,
+            recorder: Arc::clone(&self.recorder),
+            guard: current_guard().or_else(|| self.guard.clone()),
+        };
+        life.register(true);
+        life.emit(EventKind::Cloned, Some(self.id), value);
+        life
+    }
+    fn drop_with(&self, value: u32) {
+        self.emit(EventKind::Dropped, None, value);
+        self.release();
+    }
+    fn register(&self, cloned: bool) {
+        lock(&TOTALS).born(self.id, cloned);
+        if let Some(g) = &self.guard {
+            lock(g).born(self.id, cloned);
+        }
+    }
+    fn release(&self) {
+        lock(&TOTALS).died(self.id);
+        if let Some(g) = &self.guard {
+            lock(g).died(self.id);
+        }
+    }
+    fn emit(&self, kind: EventKind, source: Option<u64>, value: u32) {
+        let event = Event {
+            seq: NEXT_SEQ.fetch_add(1, Ordering::SeqCst),
+            kind,
+            id: self.id,
+            source,
+            value,
+            type_name: self.type_name,
+        };
+        self.recorder.record(&event);
+    }
+}
+
 pub struct TestLifeCycle {
     count: u32,
+    life: Life,
+}
+impl fmt::Debug for TestLifeCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TestLifeCycle").field("count", &self.count).field("id", &self.life.id).finish()
+    }
+}
+/*-- count 0, created silently as the derived Default was, later events print --*/
+impl Default for TestLifeCycle {
+    fn default() -> TestLifeCycle {
+        TestLifeCycle { count: 0, life: Life::unannounced(TEST_LIFE_CYCLE, Arc::new(StdoutRecorder)) }
+    }
 }
 /*-- any type implementing drop cannot be Copy --*/
 impl Drop for TestLifeCycle {
     fn drop(&mut self) {
-        print!("\n  TestLifeCycle instance {} dropped", self.count);
+        self.life.drop_with(self.count);
     }
 }
 /*-- any type can be Clone --*/
 impl Clone for TestLifeCycle {
     fn clone(&self) -> TestLifeCycle {
-        print!("\n  TestLifeCycle instance {} cloned", self.count);
-        TestLifeCycle { 
-            count: self.count, 
+        TestLifeCycle {
+            count: self.count,
+            life: self.life.clone_with(self.count),
         }
     }
 }
 /*-- default constructor --*/
 impl TestLifeCycle {
     pub fn new() -> TestLifeCycle {
-        print!("\n  creating instance {} of TestLifeCycle", 0);
-        TestLifeCycle { 
-            count: 0, 
-        }
+        TestLifeCycle::param_new(0)
     }
     /*-- parameterized constructor --*/
     pub fn param_new(cnt: u32) -> TestLifeCycle {
-        print!("\n  creating instance {} of TestLifeCycle", cnt);
-        TestLifeCycle { 
+        TestLifeCycle::with_recorder(cnt, Arc::new(StdoutRecorder))
+    }
+    /*-- parameterized constructor reporting to recorder --*/
+    pub fn with_recorder(cnt: u32, recorder: Arc<dyn Recorder>) -> TestLifeCycle {
+        TestLifeCycle {
             count: cnt,
+            life: Life::new(TEST_LIFE_CYCLE, recorder, cnt),
         }
     }
     /*-- value getter --*/
@@ -43,11 +381,211 @@ impl TestLifeCycle {
     pub fn set_value(&mut self, v:u32) {
         self.count = v;
     }
+    /*-- unique instance id --*/
+    pub fn id(&self) -> u64 {
+        self.life.id
+    }
+}
+
+/*-----------------------------------------------------------
+  Tracked<T> gives any value TestLifeCycle's instrumentation.
+  Deref, DerefMut, Debug, PartialEq, Eq, and Hash forward to
+  the inner T, so a Tracked<T> can stand in for T in Vec,
+  HashMap, Rc, and Arc.
+  - Rust moves are plain copies that run no code, so a move
+    can't be seen.  moved() and move_into() record a Moved
+    event where a move should show in the log.
+*/
+pub struct Tracked<T> {
+    value: T,
+    life: Life,
+}
+impl<T> Tracked<T> {
+    /*-- reports to stdout --*/
+    pub fn new(value: T) -> Tracked<T> {
+        Tracked::with_recorder(value, Arc::new(StdoutRecorder))
+    }
+    pub fn with_recorder(value: T, recorder: Arc<dyn Recorder>) -> Tracked<T> {
+        Tracked { value, life: Life::new(std::any::type_name::<Tracked<T>>(), recorder, 0) }
+    }
+    /*-- unique instance id --*/
+    pub fn id(&self) -> u64 {
+        self.life.id
+    }
+    /*-- record a move, e.g., map.insert(k, t.moved()) --*/
+    pub fn moved(self) -> Tracked<T> {
+        self.life.emit(EventKind::Moved, None, 0);
+        self
+    }
+    /*-- record a move and add to container --*/
+    pub fn move_into<C: Extend<Tracked<T>>>(self, container: &mut C) {
+        container.extend(std::iter::once(self.moved()));
+    }
+}
+impl<T> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        self.life.drop_with(0);
+    }
+}
+impl<T: Clone> Clone for Tracked<T> {
+    fn clone(&self) -> Tracked<T> {
+        Tracked { value: self.value.clone(), life: self.life.clone_with(0) }
+    }
+}
+impl<T: Default> Default for Tracked<T> {
+    fn default() -> Tracked<T> {
+        Tracked::new(T::default())
+    }
+}
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+impl<T: fmt::Debug> fmt::Debug for Tracked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+impl<T: PartialEq> PartialEq for Tracked<T> {
+    fn eq(&self, other: &Tracked<T>) -> bool {
+        self.value == other.value
+    }
+}
+impl<T: Eq> Eq for Tracked<T> {}
+impl<T: Hash> Hash for Tracked<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
 }
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use EventKind::*;
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+    #[test]
+    fn records_life_cycle() {
+        let log = EventLog::new();
+        let a = TestLifeCycle::with_recorder(1, log.clone());
+        let mut b = a.clone();
+        b.set_value(2);
+        let (ia, ib) = (a.id(), b.id());
+        drop(a);
+        drop(b);
+        assert_eq!(log.kinds(), [(Created, ia), (Cloned, ib), (Dropped, ia), (Dropped, ib)]);
+        let evs = log.events();
+        assert_eq!(evs[1].source, Some(ia));
+        assert_eq!(evs[3].value, 2);
+        assert!(evs.windows(2).all(|w| w[0].seq < w[1].seq));
+        let guard = LeakGuard::new();
+        let d = TestLifeCycle::default();
+        assert_eq!((d.get_value(), guard.counts().created), (0, 1));
+        drop(d);
+        assert_eq!(guard.finish().map(|c| c.live()), Ok(0));
+    }
+    #[test]
+    fn shared_across_threads() {
+        let log = EventLog::new();
+        let tlc = Arc::new(TestLifeCycle::with_recorder(7, log.clone()));
+        let handles: Vec<_> = (0..4).map(|_| {
+            let tlc = Arc::clone(&tlc);
+            std::thread::spawn(move || drop(tlc.as_ref().clone()))
+        }).collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        drop(tlc);
+        let evs = log.events();
+        assert_eq!(evs.len(), 10);
+        assert_eq!(evs.iter().filter(|e| e.kind == Cloned).count(), 4);
+        assert_eq!(evs.last().map(|e| e.kind), Some(Dropped));
+    }
+    #[test]
+    fn guard_finds_leaks() {
+        use std::rc::Rc;
+        struct Node {
+            _tlc: TestLifeCycle,
+            next: RefCell<Option<Rc<Node>>>,
+        }
+        let log = EventLog::new();
+        let guard = LeakGuard::new();
+        let boxed = Box::new(TestLifeCycle::with_recorder(1, log.clone()));
+        drop(boxed.clone());
+        std::mem::forget(boxed);
+        /*-- Rc cycle: a -> b -> a --*/
+        let a = Rc::new(Node { _tlc: TestLifeCycle::with_recorder(2, log.clone()), next: RefCell::new(None) });
+        let b = Rc::new(Node { _tlc: TestLifeCycle::with_recorder(3, log.clone()), next: RefCell::new(Some(a.clone())) });
+        *a.next.borrow_mut() = Some(b);
+        drop(a);
+        let report = guard.finish().unwrap_err();
+        assert_eq!(report.leaked.len(), 3);
+        assert_eq!(report.counts, Counts { created: 3, cloned: 1, dropped: 1, dropped_twice: 0 });
+        assert!(counts().live() >= 3);
+    }
+    #[test]
+    fn guard_panics_and_double_drops() {
+        let log = EventLog::new();
+        let caught = std::panic::catch_unwind(|| {
+            let _guard = LeakGuard::new();
+            std::mem::forget(TestLifeCycle::with_recorder(1, log.clone()));
+        });
+        assert!(caught.is_err());
+        /*-- bitwise copy dropped twice, as buggy unsafe code might do --*/
+        let guard = LeakGuard::new();
+        let tlc = TestLifeCycle::with_recorder(2, log.clone());
+        /*-- forgotten clone holds the extra Arc counts the copy's drop releases --*/
+        std::mem::forget(tlc.clone());
+        let copy = unsafe { std::ptr::read(&tlc) };
+        drop(tlc);
+        drop(copy);
+        let report = guard.check().unwrap_err();
+        assert_eq!((report.leaked.len(), report.dropped_twice.len()), (1, 1));
+        assert!(report.to_string().contains("1 dropped twice"));
+        let _ = guard.finish();
+        let clean = LeakGuard::new();
+        drop(TestLifeCycle::with_recorder(3, log).clone());
+        assert_eq!(clean.finish().map(|c| c.live()), Ok(0));
+    }
+    #[test]
+    fn tracked_in_containers() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashMap;
+        use std::rc::Rc;
+        let log = EventLog::new();
+        let guard = LeakGuard::new();
+        let mut v = Vec::new();
+        let a = Tracked::with_recorder(String::from("alpha"), log.clone());
+        let ia = a.id();
+        a.move_into(&mut v);
+        v[0].push('!');
+        assert_eq!(v[0].as_str(), "alpha!");
+        let mut map = HashMap::new();
+        map.insert("a", v[0].clone().moved());
+        let other = Tracked::with_recorder(String::from("alpha!"), log.clone());
+        assert_eq!(map["a"], other);
+        fn hash_of<H: Hash>(h: &H) -> u64 {
+            let mut s = DefaultHasher::new();
+            h.hash(&mut s);
+            s.finish()
+        }
+        assert_eq!(hash_of(&other), hash_of(&String::from("alpha!")));
+        assert_eq!(format!("{:?}", other), "\"alpha!\"");
+        let rc = Rc::new(Tracked::with_recorder(vec![1, 2], log.clone()));
+        assert_eq!(rc.len(), 2);
+        drop((v, map, other, rc));
+        assert_eq!(guard.finish().map(|c| (c.created, c.cloned, c.live())), Ok((3, 1, 0)));
+        let kinds: Vec<EventKind> = log.events().iter().map(|e| e.kind).collect();
+        assert_eq!(kinds[..4], [Created, Moved, Cloned, Moved]);
+        assert_eq!(log.kinds()[1], (Moved, ia));
+        assert!(log.events()[0].type_name.contains("Tracked<alloc::string::String>"));
+    }
 }